@@ -0,0 +1,47 @@
+//! `rusty_chess_perft <depth> [fen]` prints the node count below each root
+//! move followed by the total, in the same format as other engines' divide
+//! output so the two can be diffed when hunting for a move generation bug.
+
+use std::time::Instant;
+
+use rusty_chess::Game;
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let Some(depth) = args.first().and_then(|d| d.parse::<u32>().ok()) else {
+        eprintln!("usage: rusty_chess_perft <depth> [fen]");
+        std::process::exit(2);
+    };
+
+    let game = if args.len() > 1 {
+        match Game::from_fen(&args[1..].join(" ")) {
+            Ok(game) => game,
+            Err(e) => {
+                eprintln!("invalid FEN: {}", e);
+                std::process::exit(2);
+            }
+        }
+    } else {
+        Game::new()
+    };
+
+    let start = Instant::now();
+    let divide = game.divide(depth);
+    let nodes = if depth == 0 {
+        1
+    } else {
+        divide.iter().map(|(_, count)| count).sum()
+    };
+    let elapsed = start.elapsed().as_secs_f64();
+
+    for (mv, count) in &divide {
+        println!("{}: {}", mv.to_uci(), count);
+    }
+    println!();
+    println!("Nodes searched: {}", nodes);
+    println!(
+        "Time: {:.3}s ({:.0} nodes/s)",
+        elapsed,
+        nodes as f64 / elapsed.max(1e-9)
+    );
+}
@@ -0,0 +1,5 @@
+//! UCI engine for chess GUIs and tournament managers, talking over stdin and stdout.
+
+fn main() {
+    rusty_chess::protocol::run_uci(std::io::stdin().lock(), std::io::stdout());
+}
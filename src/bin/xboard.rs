@@ -0,0 +1,6 @@
+//! CECP (XBoard/WinBoard) engine for older chess GUIs and tools, talking over
+//! stdin and stdout.
+
+fn main() {
+    rusty_chess::protocol::run_xboard(std::io::stdin().lock(), std::io::stdout());
+}
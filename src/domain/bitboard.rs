@@ -0,0 +1,253 @@
+use std::sync::OnceLock;
+
+use super::Color;
+
+/// A set of squares with bit `row * 8 + col` standing for `Position { row, col }`,
+/// so bit 0 is a8 and bit 63 is h1 (see `Position::index`).
+pub type Bitboard = u64;
+
+const ROOK_DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (1, 0), (0, -1), (0, 1)];
+const BISHOP_DIRECTIONS: [(i32, i32); 4] = [(-1, -1), (-1, 1), (1, -1), (1, 1)];
+const KNIGHT_OFFSETS: [(i32, i32); 8] = [(-2, -1), (-2, 1), (-1, -2), (-1, 2), (1, -2), (1, 2), (2, -1), (2, 1)];
+const KING_OFFSETS: [(i32, i32); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+// Magic numbers for each square, found by trying sparse random numbers until
+// one mapped every blocker pattern without a harmful collision.
+const ROOK_MAGICS: [u64; 64] = [
+    0x1080_0040_0880_1020, 0x0840_0920_02C0_3000, 0x1900_2000_1040_0900, 0x0880_1000_0800_0480,
+    0x4200_1004_2008_0200, 0x8100_0201_0008_0400, 0x0200_0401_1088_6200, 0x0200_0080_4022_0411,
+    0x0404_8000_8440_0220, 0x0000_4010_0040_2000, 0x0086_0010_8122_0440, 0x0408_8008_0010_0280,
+    0x000A_0012_0104_0820, 0x8848_8002_0084_0080, 0x4001_0001_0004_0200, 0x0442_0001_0210_5084,
+    0x9080_0100_2080_4100, 0x0040_4040_0020_1009, 0x0000_8080_1000_2009, 0x2200_0900_21D0_0100,
+    0x0008_0080_0804_0080, 0x0004_0040_0201_0040, 0x0011_0400_0801_5042, 0x0000_0A00_0176_8104,
+    0x0000_8000_8020_4009, 0x2010_0041_4000_2001, 0x9800_2002_8010_0080, 0x1000_1000_8008_0080,
+    0x0442_000A_0004_9020, 0x2100_0400_8002_0080, 0x0800_1204_0090_0148, 0x0010_040A_0012_8541,
+    0x2800_8040_0080_0030, 0x1010_0020_0040_0041, 0x4000_2000_1100_4100, 0x0610_0084_1080_0800,
+    0x0400_8024_0280_0800, 0xC100_0200_8080_0400, 0x0002_0008_0200_0401, 0x0182_0858_8200_0401,
+    0x0220_2040_0080_8000, 0x2860_1000_4002_4022, 0x0001_0020_0411_0040, 0x9910_1042_000A_0020,
+    0x0004_0800_0400_8080, 0x0010_0400_0200_8080, 0x2012_0048_8102_0004, 0x8300_8424_4482_0011,
+    0x0088_4038_8201_0200, 0x0820_4000_8021_0100, 0x0110_9100_40A0_0300, 0x0801_1002_8008_0480,
+    0x0242_0090_0820_0600, 0x1002_0004_8950_0200, 0x0040_8002_0001_0080, 0x0091_8000_4100_0080,
+    0x0000_2093_0048_8001, 0x04C1_0024_1482_4001, 0x0200_2000_0B00_1041, 0x7000_1000_0420_0901,
+    0x8002_0020_0410_0802, 0x3001_0002_084C_0007, 0x0888_2218_0081_3004, 0x4000_0028_4084_0112,
+];
+
+const BISHOP_MAGICS: [u64; 64] = [
+    0xA010_0411_0800_3100, 0x0060_8202_0A00_2900, 0x6810_0106_1920_0000, 0x0828_1A05_2000_0408,
+    0x0001_1040_0100_0400, 0x0018_9010_0804_8400, 0x0004_0A02_1024_5280, 0x0002_0021_0808_A402,
+    0x9140_0484_1082_1200, 0x0800_0910_1082_0041, 0x2050_4804_8322_02C0, 0x0100_0914_0108_1000,
+    0x8021_0111_4000_0012, 0x0810_0208_0445_0400, 0x208B_0542_1090_08A2, 0x0080_084A_0804_0204,
+    0x0040_E2A8_0811_244C, 0x2505_0220_0800_8108, 0x0430_2201_0042_0040, 0x010A_0404_2022_0040,
+    0x1105_0002_9040_0000, 0x0093_0012_0082_2120, 0x4000_A620_4804_3004, 0x2801_2004_8A01_5004,
+    0x0060_9000_2A02_0814, 0x4404_2000_2408_00D0, 0x0110_2800_040A_4400, 0x1004_0800_8022_0040,
+    0x0001_0010_1100_4024, 0x0010_0440_0080_5040, 0x0914_0412_0082_0100, 0x0004_8210_1282_1480,
+    0x0024_0405_00C0_5021, 0x0088_6110_0208_0200, 0x0116_080A_0004_0020, 0x4000_0200_8008_0080,
+    0x2450_4501_4084_0040, 0x0000_8802_0148_4100, 0x0222_0204_0402_0092, 0x8081_1106_0000_2E00,
+    0x2842_1011_0500_0801, 0x1100_8090_0800_1025, 0x0002_0202_221C_0400, 0x0422_0140_2200_9020,
+    0x0210_0461_0210_0C00, 0xC004_0080_8202_9102, 0x00AA_4618_0110_1200, 0x0404_0800_8020_1108,
+    0x0205_4210_8C20_5002, 0x0410_5448_0410_0100, 0x0040_9108_4110_0000, 0x0400_2000_4202_1100,
+    0x0000_4204_8504_00C0, 0x0200_1004_10A4_2102, 0x1040_0208_0121_0102, 0x0805_0404_1042_0000,
+    0x2884_8041_3010_0200, 0x800C_2622_0124_2000, 0x1058_0001_9410_8800, 0x0014_2210_5442_0204,
+    0x0104_0000_12A0_2200, 0x0200_8810_0330_0100, 0x0140_4002_0284_0100, 0x0402_0208_0101_0201,
+];
+
+/// Iterates over the squares of a bitboard, lowest index first.
+pub fn squares(bitboard: Bitboard) -> Squares {
+    Squares(bitboard)
+}
+
+pub struct Squares(Bitboard);
+
+impl Iterator for Squares {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.0 == 0 {
+            return None;
+        }
+        let square = self.0.trailing_zeros() as usize;
+        self.0 &= self.0 - 1;
+        Some(square)
+    }
+}
+
+pub fn knight_attacks(square: usize) -> Bitboard {
+    tables().knight[square]
+}
+
+pub fn king_attacks(square: usize) -> Bitboard {
+    tables().king[square]
+}
+
+/// Squares a pawn of `color` on `square` captures on.
+pub fn pawn_attacks(color: Color, square: usize) -> Bitboard {
+    tables().pawn[color as usize][square]
+}
+
+/// Squares a rook on `square` attacks, stopping at (and including) the first
+/// occupied square in each direction.
+pub fn rook_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.sliding[tables.rook[square].index(occupied)]
+}
+
+pub fn bishop_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    let tables = tables();
+    tables.sliding[tables.bishop[square].index(occupied)]
+}
+
+pub fn queen_attacks(square: usize, occupied: Bitboard) -> Bitboard {
+    rook_attacks(square, occupied) | bishop_attacks(square, occupied)
+}
+
+/// Squares strictly between `a` and `b` if they share a rank, file or
+/// diagonal, otherwise empty.
+pub fn between(a: usize, b: usize) -> Bitboard {
+    tables().between[a][b]
+}
+
+/// The whole rank, file or diagonal through `a` and `b`, or empty if they
+/// aren't aligned.
+pub fn line(a: usize, b: usize) -> Bitboard {
+    tables().line[a][b]
+}
+
+/// Maps the blockers relevant to a slider on one square to its slot in
+/// `AttackTables::sliding` by multiplying them with a magic number.
+struct Magic {
+    mask: Bitboard,
+    magic: u64,
+    shift: u32,
+    offset: usize,
+}
+
+impl Magic {
+    fn index(&self, occupied: Bitboard) -> usize {
+        self.offset + ((occupied & self.mask).wrapping_mul(self.magic) >> self.shift) as usize
+    }
+}
+
+struct AttackTables {
+    knight: [Bitboard; 64],
+    king: [Bitboard; 64],
+    pawn: [[Bitboard; 64]; 2],
+    rook: Vec<Magic>,
+    bishop: Vec<Magic>,
+    sliding: Vec<Bitboard>,
+    between: Vec<[Bitboard; 64]>,
+    line: Vec<[Bitboard; 64]>,
+}
+
+/// The tables are built on first use.
+fn tables() -> &'static AttackTables {
+    static TABLES: OnceLock<AttackTables> = OnceLock::new();
+    TABLES.get_or_init(AttackTables::new)
+}
+
+impl AttackTables {
+    fn new() -> Self {
+        let mut knight = [0; 64];
+        let mut king = [0; 64];
+        let mut pawn = [[0; 64]; 2];
+        for square in 0..64 {
+            knight[square] = offset_attacks(square, &KNIGHT_OFFSETS);
+            king[square] = offset_attacks(square, &KING_OFFSETS);
+            pawn[Color::White as usize][square] = offset_attacks(square, &[(-1, -1), (-1, 1)]);
+            pawn[Color::Black as usize][square] = offset_attacks(square, &[(1, -1), (1, 1)]);
+        }
+
+        let mut sliding = Vec::new();
+        let rook = (0..64)
+            .map(|square| fill_magic(square, &ROOK_DIRECTIONS, ROOK_MAGICS[square], &mut sliding))
+            .collect();
+        let bishop = (0..64)
+            .map(|square| fill_magic(square, &BISHOP_DIRECTIONS, BISHOP_MAGICS[square], &mut sliding))
+            .collect();
+
+        let mut between = vec![[0; 64]; 64];
+        let mut line = vec![[0; 64]; 64];
+        for a in 0..64 {
+            for b in 0..64 {
+                if a == b {
+                    continue;
+                }
+                let (a_bit, b_bit) = (1u64 << a, 1u64 << b);
+                for directions in [&ROOK_DIRECTIONS, &BISHOP_DIRECTIONS] {
+                    if slow_sliding_attacks(a, 0, directions) & b_bit == 0 {
+                        continue;
+                    }
+                    between[a][b] = slow_sliding_attacks(a, b_bit, directions) & slow_sliding_attacks(b, a_bit, directions);
+                    line[a][b] = (slow_sliding_attacks(a, 0, directions) & slow_sliding_attacks(b, 0, directions)) | a_bit | b_bit;
+                }
+            }
+        }
+
+        AttackTables { knight, king, pawn, rook, bishop, sliding, between, line }
+    }
+}
+
+fn offset_attacks(square: usize, offsets: &[(i32, i32)]) -> Bitboard {
+    let (row, col) = ((square / 8) as i32, (square % 8) as i32);
+    offsets
+        .iter()
+        .map(|&(row_delta, col_delta)| (row + row_delta, col + col_delta))
+        .filter(|&(row, col)| (0..8).contains(&row) && (0..8).contains(&col))
+        .fold(0, |attacks, (row, col)| attacks | 1 << (row * 8 + col))
+}
+
+/// Ray-walking slider attacks, used to fill the lookup tables.
+fn slow_sliding_attacks(square: usize, occupied: Bitboard, directions: &[(i32, i32)]) -> Bitboard {
+    let mut attacks = 0;
+    for &(row_delta, col_delta) in directions {
+        let mut row = (square / 8) as i32 + row_delta;
+        let mut col = (square % 8) as i32 + col_delta;
+        while (0..8).contains(&row) && (0..8).contains(&col) {
+            let bit = 1u64 << (row * 8 + col);
+            attacks |= bit;
+            if occupied & bit != 0 {
+                break;
+            }
+            row += row_delta;
+            col += col_delta;
+        }
+    }
+    attacks
+}
+
+/// Squares whose occupancy changes a slider's attacks: its rays without the
+/// last square, since a piece on the edge of the board blocks nothing.
+fn relevant_blockers(square: usize, directions: &[(i32, i32)]) -> Bitboard {
+    let mut mask = 0;
+    for &(row_delta, col_delta) in directions {
+        let mut row = (square / 8) as i32 + row_delta;
+        let mut col = (square % 8) as i32 + col_delta;
+        while (0..8).contains(&(row + row_delta)) && (0..8).contains(&(col + col_delta)) {
+            mask |= 1u64 << (row * 8 + col);
+            row += row_delta;
+            col += col_delta;
+        }
+    }
+    mask
+}
+
+/// Appends the attacks of a slider on `square` for every blocker pattern to
+/// `sliding`, at the slots `magic` maps the patterns to.
+fn fill_magic(square: usize, directions: &[(i32, i32)], magic: u64, sliding: &mut Vec<Bitboard>) -> Magic {
+    let mask = relevant_blockers(square, directions);
+    let shift = 64 - mask.count_ones();
+    let entry = Magic { mask, magic, shift, offset: sliding.len() };
+    sliding.resize(sliding.len() + (1 << mask.count_ones()), 0);
+
+    let mut blockers: Bitboard = 0;
+    loop {
+        let attacks = slow_sliding_attacks(square, blockers, directions);
+        let slot = &mut sliding[entry.index(blockers)];
+        assert!(*slot == 0 || *slot == attacks, "bad magic for square {}", square);
+        *slot = attacks;
+
+        blockers = blockers.wrapping_sub(mask) & mask;
+        if blockers == 0 {
+            return entry;
+        }
+    }
+}
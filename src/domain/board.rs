@@ -1,17 +1,31 @@
-use super::{Piece, Position, PieceType, Color};
+use super::{Piece, Position, PieceType, Color, Bitboard, squares, between, pawn_attacks, knight_attacks, king_attacks, rook_attacks, bishop_attacks};
+use super::zobrist::piece_key;
 
 pub type BoardState = [[Option<Piece>; 8]; 8];
 
+/// Squares with `(row + col)` even, i.e. a8, c8, ..., h1.
+const LIGHT_SQUARES: Bitboard = 0xAA55_AA55_AA55_AA55;
+
+/// The square-by-square `state` is kept for the UI and notation code; move
+/// generation and attack detection work on the bitboards, which hold the same
+/// pieces indexed by `PieceType as usize` and `Color as usize`.
 #[derive(Debug, Clone)]
 pub struct Board {
     state: BoardState,
+    pieces: [Bitboard; 6],
+    colors: [Bitboard; 2],
+    zobrist_key: u64,
+}
+
+impl Default for Board {
+    fn default() -> Self {
+        Board::new()
+    }
 }
 
 impl Board {
     pub fn new() -> Self {
-        let mut board = Board {
-            state: [[None; 8]; 8],
-        };
+        let mut board = Board::empty();
         board.setup_initial_position();
         board
     }
@@ -19,6 +33,9 @@ impl Board {
     pub fn empty() -> Self {
         Board {
             state: [[None; 8]; 8],
+            pieces: [0; 6],
+            colors: [0; 2],
+            zobrist_key: 0,
         }
     }
 
@@ -28,11 +45,11 @@ impl Board {
             PieceType::King, PieceType::Bishop, PieceType::Knight, PieceType::Rook
         ];
 
-        for col in 0..8 {
-            self.state[0][col] = Some(Piece::new(back_row[col], Color::Black));
-            self.state[1][col] = Some(Piece::new(PieceType::Pawn, Color::Black));
-            self.state[6][col] = Some(Piece::new(PieceType::Pawn, Color::White));
-            self.state[7][col] = Some(Piece::new(back_row[col], Color::White));
+        for (col, &piece_type) in back_row.iter().enumerate() {
+            self.set_piece(Position { row: 0, col }, Some(Piece::new(piece_type, Color::Black)));
+            self.set_piece(Position { row: 1, col }, Some(Piece::new(PieceType::Pawn, Color::Black)));
+            self.set_piece(Position { row: 6, col }, Some(Piece::new(PieceType::Pawn, Color::White)));
+            self.set_piece(Position { row: 7, col }, Some(Piece::new(piece_type, Color::White)));
         }
     }
 
@@ -45,12 +62,23 @@ impl Board {
     }
 
     pub fn set_piece(&mut self, pos: Position, piece: Option<Piece>) -> bool {
-        if pos.is_valid() {
-            self.state[pos.row][pos.col] = piece;
-            true
-        } else {
-            false
+        if !pos.is_valid() {
+            return false;
+        }
+
+        let bit: Bitboard = 1 << pos.index();
+        if let Some(old) = self.state[pos.row][pos.col] {
+            self.pieces[old.piece_type as usize] &= !bit;
+            self.colors[old.color as usize] &= !bit;
+            self.zobrist_key ^= piece_key(old, pos.index());
         }
+        if let Some(new) = piece {
+            self.pieces[new.piece_type as usize] |= bit;
+            self.colors[new.color as usize] |= bit;
+            self.zobrist_key ^= piece_key(new, pos.index());
+        }
+        self.state[pos.row][pos.col] = piece;
+        true
     }
 
     pub fn move_piece(&mut self, from: Position, to: Position) -> Option<Piece> {
@@ -61,8 +89,8 @@ impl Board {
         let piece = self.state[from.row][from.col]?;
         let captured = self.state[to.row][to.col];
         
-        self.state[from.row][from.col] = None;
-        self.state[to.row][to.col] = Some(piece);
+        self.set_piece(from, None);
+        self.set_piece(to, Some(piece));
         
         captured
     }
@@ -70,4 +98,121 @@ impl Board {
     pub fn get_state(&self) -> &BoardState {
         &self.state
     }
-}
\ No newline at end of file
+
+    /// Zobrist key of the pieces alone, updated by every `set_piece`. See
+    /// `Game::zobrist_key` for the key of the whole position.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+
+    /// Squares holding pieces of the given type and color.
+    pub fn pieces(&self, piece_type: PieceType, color: Color) -> Bitboard {
+        self.pieces[piece_type as usize] & self.colors[color as usize]
+    }
+
+    /// Squares holding pieces of `color`.
+    pub fn occupancy(&self, color: Color) -> Bitboard {
+        self.colors[color as usize]
+    }
+
+    pub fn occupied(&self) -> Bitboard {
+        self.colors[0] | self.colors[1]
+    }
+
+    pub fn find_king(&self, color: Color) -> Option<Position> {
+        squares(self.pieces(PieceType::King, color)).next().and_then(Position::from_index)
+    }
+
+    /// True when neither side can possibly deliver mate: bare kings, a single
+    /// knight, or any number of bishops that all stand on squares of one color.
+    pub fn has_insufficient_material(&self) -> bool {
+        let heavy = self.pieces[PieceType::Pawn as usize] | self.pieces[PieceType::Rook as usize] | self.pieces[PieceType::Queen as usize];
+        if heavy != 0 {
+            return false;
+        }
+
+        let bishops = self.pieces[PieceType::Bishop as usize];
+        match self.pieces[PieceType::Knight as usize].count_ones() {
+            0 => bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0,
+            1 => bishops == 0,
+            _ => false,
+        }
+    }
+
+    /// Whether `color` has the material to mate in some line of play. A lone
+    /// minor piece, or bishops that all stand on squares of one color, can
+    /// only mate when the opponent has pieces of their own to hem their king
+    /// in.
+    pub fn has_mating_material(&self, color: Color) -> bool {
+        let own = self.colors[color as usize];
+        let heavy = self.pieces[PieceType::Pawn as usize] | self.pieces[PieceType::Rook as usize] | self.pieces[PieceType::Queen as usize];
+        if heavy & own != 0 {
+            return true;
+        }
+
+        let knights = self.pieces[PieceType::Knight as usize] & own;
+        let bishops = self.pieces[PieceType::Bishop as usize] & own;
+        let needs_help = match (knights | bishops).count_ones() {
+            0 => return false,
+            1 => true,
+            _ => knights == 0 && (bishops & LIGHT_SQUARES == 0 || bishops & !LIGHT_SQUARES == 0),
+        };
+
+        let opponent_pieces = self.colors[color.opposite() as usize] & !self.pieces[PieceType::King as usize];
+        !needs_help || opponent_pieces != 0
+    }
+
+    /// Pieces of `by` attacking `square` with the given occupancy, which lets
+    /// callers ask about positions where some pieces have moved away.
+    pub fn attackers_to(&self, square: usize, by: Color, occupied: Bitboard) -> Bitboard {
+        let rooks_and_queens = self.pieces[PieceType::Rook as usize] | self.pieces[PieceType::Queen as usize];
+        let bishops_and_queens = self.pieces[PieceType::Bishop as usize] | self.pieces[PieceType::Queen as usize];
+
+        let attackers = (pawn_attacks(by.opposite(), square) & self.pieces[PieceType::Pawn as usize])
+            | (knight_attacks(square) & self.pieces[PieceType::Knight as usize])
+            | (king_attacks(square) & self.pieces[PieceType::King as usize])
+            | (rook_attacks(square, occupied) & rooks_and_queens)
+            | (bishop_attacks(square, occupied) & bishops_and_queens);
+        attackers & self.colors[by as usize]
+    }
+
+    /// Returns true if any piece of `by` attacks `target`, regardless of what stands on it.
+    pub fn is_square_attacked(&self, target: Position, by: Color) -> bool {
+        target.is_valid() && self.attackers_to(target.index(), by, self.occupied()) != 0
+    }
+
+    /// Checks that every square strictly between `from` and `to` is empty.
+    /// The squares must share a rank, file or diagonal.
+    pub fn is_path_clear(&self, from: Position, to: Position) -> bool {
+        between(from.index(), to.index()) & self.occupied() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn insufficient(placement: &str) -> bool {
+        Board::from_fen_placement(placement).unwrap().has_insufficient_material()
+    }
+
+    #[test]
+    fn bare_kings_and_a_single_minor_piece_cannot_mate() {
+        assert!(insufficient("4k3/8/8/8/8/8/8/4K3"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/4KN2"));
+        assert!(insufficient("4k3/8/8/8/8/8/8/2B1K3"));
+    }
+
+    #[test]
+    fn bishops_only_draw_when_all_on_one_colour() {
+        // c1 and f8 are both dark squares, c1 and c8 are not.
+        assert!(insufficient("5b2/4k3/8/8/8/8/8/2B1K3"));
+        assert!(!insufficient("2b5/4k3/8/8/8/8/8/2B1K3"));
+    }
+
+    #[test]
+    fn two_knights_or_a_pawn_are_enough() {
+        assert!(!insufficient("4k3/8/8/8/8/8/8/1N2KN2"));
+        assert!(!insufficient("4k3/8/8/8/8/8/4P3/4K3"));
+    }
+}
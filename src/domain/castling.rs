@@ -0,0 +1,110 @@
+use super::{Color, Position};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CastleSide {
+    KingSide,
+    QueenSide,
+}
+
+impl CastleSide {
+    /// Home square of the rook taking part in castling on this side.
+    pub fn rook_from(self, color: Color) -> Position {
+        let col = match self {
+            CastleSide::KingSide => 7,
+            CastleSide::QueenSide => 0,
+        };
+        Position { row: home_row(color), col }
+    }
+
+    /// Square the rook lands on once castling is done.
+    pub fn rook_to(self, color: Color) -> Position {
+        let col = match self {
+            CastleSide::KingSide => 5,
+            CastleSide::QueenSide => 3,
+        };
+        Position { row: home_row(color), col }
+    }
+
+    /// Square the king lands on once castling is done.
+    pub fn king_to(self, color: Color) -> Position {
+        let col = match self {
+            CastleSide::KingSide => 6,
+            CastleSide::QueenSide => 2,
+        };
+        Position { row: home_row(color), col }
+    }
+}
+
+pub fn king_home(color: Color) -> Position {
+    Position { row: home_row(color), col: 4 }
+}
+
+fn home_row(color: Color) -> usize {
+    match color {
+        Color::White => 7,
+        Color::Black => 0,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+impl CastlingRights {
+    pub fn all() -> Self {
+        CastlingRights {
+            white_kingside: true,
+            white_queenside: true,
+            black_kingside: true,
+            black_queenside: true,
+        }
+    }
+
+    pub fn none() -> Self {
+        CastlingRights {
+            white_kingside: false,
+            white_queenside: false,
+            black_kingside: false,
+            black_queenside: false,
+        }
+    }
+
+    pub fn has(&self, color: Color, side: CastleSide) -> bool {
+        match (color, side) {
+            (Color::White, CastleSide::KingSide) => self.white_kingside,
+            (Color::White, CastleSide::QueenSide) => self.white_queenside,
+            (Color::Black, CastleSide::KingSide) => self.black_kingside,
+            (Color::Black, CastleSide::QueenSide) => self.black_queenside,
+        }
+    }
+
+    pub fn remove(&mut self, color: Color, side: CastleSide) {
+        match (color, side) {
+            (Color::White, CastleSide::KingSide) => self.white_kingside = false,
+            (Color::White, CastleSide::QueenSide) => self.white_queenside = false,
+            (Color::Black, CastleSide::KingSide) => self.black_kingside = false,
+            (Color::Black, CastleSide::QueenSide) => self.black_queenside = false,
+        }
+    }
+
+    /// Drops any right that depends on a piece standing on `pos`. Called for both
+    /// the source and destination of every move, so a king or rook moving away and
+    /// a rook being captured on its home square are handled the same way.
+    pub fn update_for_square(&mut self, pos: Position) {
+        for color in [Color::White, Color::Black] {
+            if pos == king_home(color) {
+                self.remove(color, CastleSide::KingSide);
+                self.remove(color, CastleSide::QueenSide);
+            }
+            for side in [CastleSide::KingSide, CastleSide::QueenSide] {
+                if pos == side.rook_from(color) {
+                    self.remove(color, side);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use super::{PieceType, Position};
+
+/// A move request as made by a player: source, destination and, for pawns
+/// reaching the last rank, the piece to promote to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    pub promotion: Option<PieceType>,
+}
+
+impl Move {
+    pub fn new(from: Position, to: Position) -> Self {
+        Move { from, to, promotion: None }
+    }
+
+    pub fn with_promotion(from: Position, to: Position, promotion: PieceType) -> Self {
+        Move { from, to, promotion: Some(promotion) }
+    }
+
+    /// Parses UCI long algebraic notation such as `e2e4`, `e7e8q` or `e1g1`.
+    /// Only the syntax is checked; see `Game::apply_uci_move` for legality.
+    pub fn from_uci(uci: &str) -> Option<Self> {
+        if !uci.is_ascii() || (uci.len() != 4 && uci.len() != 5) {
+            return None;
+        }
+
+        let from = Position::from_chess_notation(&uci[0..2])?;
+        let to = Position::from_chess_notation(&uci[2..4])?;
+        let promotion = match uci.as_bytes().get(4) {
+            None => None,
+            Some(b'q') => Some(PieceType::Queen),
+            Some(b'r') => Some(PieceType::Rook),
+            Some(b'b') => Some(PieceType::Bishop),
+            Some(b'n') => Some(PieceType::Knight),
+            Some(_) => return None,
+        };
+
+        Some(Move { from, to, promotion })
+    }
+
+    /// UCI long algebraic notation; castling is written as the king's move.
+    pub fn to_uci(self) -> String {
+        let promotion = match self.promotion {
+            Some(PieceType::Queen) => "q",
+            Some(PieceType::Rook) => "r",
+            Some(PieceType::Bishop) => "b",
+            Some(PieceType::Knight) => "n",
+            _ => "",
+        };
+        format!("{}{}{}", self.from.to_chess_notation(), self.to.to_chess_notation(), promotion)
+    }
+}
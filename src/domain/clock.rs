@@ -0,0 +1,433 @@
+use std::fmt;
+use std::time::{Duration, Instant};
+
+use super::{Color, Game};
+
+/// Time a player gets back for each move they make.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeBonus {
+    None,
+    /// Fischer increment, added after every move.
+    Increment(Duration),
+    /// Bronstein delay: the time spent on a move is given back afterwards, up
+    /// to this much, so the clock never gains time.
+    Delay(Duration),
+}
+
+/// One stage of a time control: `time` for the next `moves` moves, or for the
+/// rest of the game when `moves` is `None`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimePeriod {
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: TimeBonus,
+}
+
+/// Periods played one after the other, each adding its time to the clock when
+/// it begins. A last period with a move count repeats for as long as the game
+/// lasts, so a single period of 40 moves in two hours gives two more hours
+/// after every 40 moves.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeControl {
+    periods: Vec<TimePeriod>,
+}
+
+impl TimeControl {
+    /// Panics if `periods` is empty.
+    pub fn new(periods: Vec<TimePeriod>) -> Self {
+        assert!(!periods.is_empty(), "a time control needs at least one period");
+        TimeControl { periods }
+    }
+
+    /// The whole game in `time`, with nothing added per move.
+    pub fn sudden_death(time: Duration) -> Self {
+        TimeControl::new(vec![TimePeriod { moves: None, time, bonus: TimeBonus::None }])
+    }
+
+    pub fn increment(time: Duration, increment: Duration) -> Self {
+        TimeControl::new(vec![TimePeriod { moves: None, time, bonus: TimeBonus::Increment(increment) }])
+    }
+
+    pub fn delay(time: Duration, delay: Duration) -> Self {
+        TimeControl::new(vec![TimePeriod { moves: None, time, bonus: TimeBonus::Delay(delay) }])
+    }
+
+    pub fn periods(&self) -> &[TimePeriod] {
+        &self.periods
+    }
+
+    /// Short human readable form, e.g. `40 moves in 90 min, then 30 min, +30 s`.
+    pub fn description(&self) -> String {
+        let periods: Vec<String> = self
+            .periods
+            .iter()
+            .map(|period| {
+                let time = describe_duration(period.time);
+                match period.moves {
+                    Some(moves) if moves > 0 => format!("{} moves in {}", moves, time),
+                    _ => time,
+                }
+            })
+            .collect();
+
+        let mut description = periods.join(", then ");
+        // The bonus nearly always stays the same across periods, so only the
+        // first period's is named.
+        match self.periods[0].bonus {
+            TimeBonus::None => {}
+            TimeBonus::Increment(increment) => description.push_str(&format!(" +{}", describe_duration(increment))),
+            TimeBonus::Delay(delay) => description.push_str(&format!(", {} delay", describe_duration(delay))),
+        }
+        description
+    }
+
+    /// The period in which a player who has made `moves_made` moves makes
+    /// their next one.
+    fn current_period(&self, moves_made: u32) -> &TimePeriod {
+        let mut start = 0;
+        for period in &self.periods {
+            match period.moves {
+                Some(moves) if moves > 0 && moves_made >= start + moves => start += moves,
+                _ => return period,
+            }
+        }
+        &self.periods[self.periods.len() - 1]
+    }
+
+    /// The period a player starts once `moves_made` moves have completed the
+    /// one before it, if they just have.
+    fn period_starting_after(&self, moves_made: u32) -> Option<&TimePeriod> {
+        let mut start = 0;
+        for (i, period) in self.periods.iter().enumerate() {
+            let moves = period.moves.filter(|&moves| moves > 0)?;
+            if i + 1 == self.periods.len() {
+                let into_period = moves_made.checked_sub(start)?;
+                return (into_period > 0 && into_period % moves == 0).then_some(period);
+            }
+            start += moves;
+            if moves_made <= start {
+                return (moves_made == start).then(|| &self.periods[i + 1]);
+            }
+        }
+        None
+    }
+}
+
+/// The PGN `TimeControl` tag value: periods separated by `:`, each written as
+/// `moves/seconds` or just `seconds` for sudden death, followed by `+seconds`
+/// of increment. The PGN standard has no notation for a delay, which is
+/// written as `d` and the seconds, as some other programs do.
+impl fmt::Display for TimeControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, period) in self.periods.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            if let Some(moves) = period.moves.filter(|&moves| moves > 0) {
+                write!(f, "{}/", moves)?;
+            }
+            write!(f, "{}", period.time.as_secs())?;
+            match period.bonus {
+                TimeBonus::None => {}
+                TimeBonus::Increment(increment) => write!(f, "+{}", increment.as_secs())?,
+                TimeBonus::Delay(delay) => write!(f, "d{}", delay.as_secs())?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn describe_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 && seconds.is_multiple_of(60) {
+        format!("{} min", seconds / 60)
+    } else {
+        format!("{} s", seconds)
+    }
+}
+
+/// Formats a clock reading as `h:mm:ss`, the form used by PGN `%clk`
+/// comments.
+pub fn format_clock_time(time: Duration) -> String {
+    let seconds = time.as_secs();
+    format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+}
+
+/// The state `take_back` returns to.
+#[derive(Debug, Clone, Copy)]
+struct ClockSnapshot {
+    remaining: [Duration; 2],
+    moves_made: [u32; 2],
+    running: bool,
+}
+
+/// A chess clock for both players. The caller passes the current time to
+/// every method, which keeps the clock free of any timer of its own.
+///
+/// The clock starts with the first move: the player who makes it isn't timed
+/// for it, and their opponent's time starts running.
+#[derive(Debug, Clone)]
+pub struct Clock {
+    control: TimeControl,
+    /// Time left at the start of each player's current turn, indexed by
+    /// `Color as usize`.
+    remaining: [Duration; 2],
+    moves_made: [u32; 2],
+    turn: Color,
+    /// When the current turn started; `None` while the clock is stopped.
+    turn_started: Option<Instant>,
+    /// Ply of the game the clock was set up at; `move_times` starts with the
+    /// move after it.
+    first_ply: usize,
+    /// Time the mover had left after each move, oldest first.
+    move_times: Vec<Duration>,
+    history: Vec<ClockSnapshot>,
+}
+
+impl Clock {
+    pub fn new(control: TimeControl, first_to_move: Color) -> Self {
+        let time = control.current_period(0).time;
+        Clock {
+            control,
+            remaining: [time; 2],
+            moves_made: [0; 2],
+            turn: first_to_move,
+            turn_started: None,
+            first_ply: 0,
+            move_times: Vec::new(),
+            history: Vec::new(),
+        }
+    }
+
+    /// A clock for the rest of `game`, starting from the position on its
+    /// board rather than from the first move.
+    pub fn for_game(control: TimeControl, game: &Game) -> Self {
+        Clock {
+            first_ply: game.current_ply(),
+            ..Clock::new(control, game.current_player())
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    /// The player whose time runs once the clock is going.
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.turn_started.is_some()
+    }
+
+    /// Time `color` has left at `now`.
+    pub fn remaining(&self, color: Color, now: Instant) -> Duration {
+        let remaining = self.remaining[color as usize];
+        match self.turn_started {
+            Some(started) if color == self.turn => remaining.saturating_sub(now.saturating_duration_since(started)),
+            _ => remaining,
+        }
+    }
+
+    /// The player whose time ran out, if any.
+    pub fn flagged(&self, now: Instant) -> Option<Color> {
+        (self.is_running() && self.remaining(self.turn, now).is_zero()).then_some(self.turn)
+    }
+
+    /// Time the mover had left after each move since the clock was created,
+    /// oldest first.
+    pub fn move_times(&self) -> &[Duration] {
+        &self.move_times
+    }
+
+    /// Time the mover had left after the move that reached `ply` of the game,
+    /// if the clock was running for it.
+    pub fn time_after_ply(&self, ply: usize) -> Option<Duration> {
+        let index = ply.checked_sub(self.first_ply + 1)?;
+        self.move_times.get(index).copied()
+    }
+
+    /// Ends the turn of the player to move after they made a move: charges
+    /// the time they used, adds their bonus and any time for a new period, and
+    /// starts their opponent's time.
+    pub fn press(&mut self, now: Instant) {
+        self.history.push(ClockSnapshot {
+            remaining: self.remaining,
+            moves_made: self.moves_made,
+            running: self.is_running(),
+        });
+
+        let mover = self.turn as usize;
+        let used = self.turn_started.map_or(Duration::ZERO, |started| now.saturating_duration_since(started));
+        let mut remaining = self.remaining[mover].saturating_sub(used);
+
+        match self.control.current_period(self.moves_made[mover]).bonus {
+            TimeBonus::None => {}
+            TimeBonus::Increment(increment) => remaining += increment,
+            TimeBonus::Delay(delay) => remaining += used.min(delay),
+        }
+
+        self.moves_made[mover] += 1;
+        if let Some(period) = self.control.period_starting_after(self.moves_made[mover]) {
+            remaining += period.time;
+        }
+
+        self.remaining[mover] = remaining;
+        self.move_times.push(remaining);
+        self.turn = self.turn.opposite();
+        self.turn_started = Some(now);
+    }
+
+    /// Stops the clock, e.g. when the game is over, keeping the time the
+    /// player to move has used so far.
+    pub fn stop(&mut self, now: Instant) {
+        self.remaining[self.turn as usize] = self.remaining(self.turn, now);
+        self.turn_started = None;
+    }
+
+    /// Starts the time of the player to move running again after `stop`.
+    pub fn resume(&mut self, now: Instant) {
+        if self.turn_started.is_none() {
+            self.turn_started = Some(now);
+        }
+    }
+
+    /// Gives the turn to the other player without adding or refunding time,
+    /// for taking back a move made before the clock was set up. The time the
+    /// player to move used so far stays used.
+    pub fn switch_turn(&mut self, now: Instant) {
+        let running = self.is_running();
+        self.stop(now);
+        self.turn = self.turn.opposite();
+        self.first_ply = self.first_ply.saturating_sub(1);
+        self.turn_started = running.then_some(now);
+    }
+
+    /// Undoes the last `press`, giving the player who made that move their
+    /// time back as it was when their turn began. Their time starts running
+    /// again from `now` if the clock was going at that point.
+    pub fn take_back(&mut self, now: Instant) -> bool {
+        let Some(snapshot) = self.history.pop() else {
+            return false;
+        };
+
+        self.remaining = snapshot.remaining;
+        self.moves_made = snapshot.moves_made;
+        self.turn = self.turn.opposite();
+        self.turn_started = snapshot.running.then_some(now);
+        self.move_times.pop();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn secs(seconds: u64) -> Duration {
+        Duration::from_secs(seconds)
+    }
+
+    /// Presses the clock at each of `times`, in seconds after `start`.
+    fn press_at(clock: &mut Clock, start: Instant, times: &[u64]) {
+        for &time in times {
+            clock.press(start + secs(time));
+        }
+    }
+
+    #[test]
+    fn increment_is_added_after_every_move() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::increment(secs(60), secs(2)), Color::White);
+        press_at(&mut clock, start, &[0, 5]);
+
+        assert_eq!(clock.move_times(), [secs(62), secs(57)]);
+        assert_eq!(clock.turn(), Color::White);
+        assert_eq!(clock.remaining(Color::White, start + secs(10)), secs(57));
+        assert_eq!(clock.remaining(Color::Black, start + secs(10)), secs(57));
+    }
+
+    #[test]
+    fn delay_never_gains_time() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::delay(secs(60), secs(5)), Color::White);
+        press_at(&mut clock, start, &[0, 3, 4, 14]);
+
+        assert_eq!(clock.move_times(), [secs(60), secs(60), secs(60), secs(55)]);
+    }
+
+    #[test]
+    fn next_period_adds_its_time() {
+        let start = Instant::now();
+        let control = TimeControl::new(vec![
+            TimePeriod { moves: Some(2), time: secs(90), bonus: TimeBonus::Increment(secs(30)) },
+            TimePeriod { moves: None, time: secs(30), bonus: TimeBonus::Increment(secs(30)) },
+        ]);
+        let mut clock = Clock::new(control, Color::White);
+        press_at(&mut clock, start, &[0, 10, 15]);
+
+        assert_eq!(clock.move_times(), [secs(120), secs(110), secs(175)]);
+    }
+
+    #[test]
+    fn last_period_repeats() {
+        let start = Instant::now();
+        let control = TimeControl::new(vec![TimePeriod { moves: Some(2), time: secs(10), bonus: TimeBonus::None }]);
+        let mut clock = Clock::new(control, Color::White);
+        press_at(&mut clock, start, &[0, 1, 2, 3, 4, 5]);
+
+        assert_eq!(clock.move_times(), [secs(10), secs(9), secs(19), secs(18), secs(18), secs(17)]);
+    }
+
+    #[test]
+    fn flags_when_time_runs_out() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::sudden_death(secs(60)), Color::White);
+        assert_eq!(clock.flagged(start + secs(100)), None);
+
+        press_at(&mut clock, start, &[0]);
+        assert_eq!(clock.flagged(start + secs(59)), None);
+        assert_eq!(clock.flagged(start + secs(60)), Some(Color::Black));
+    }
+
+    #[test]
+    fn take_back_restores_the_movers_time() {
+        let start = Instant::now();
+        let control = TimeControl::new(vec![
+            TimePeriod { moves: Some(2), time: secs(90), bonus: TimeBonus::Increment(secs(30)) },
+            TimePeriod { moves: None, time: secs(30), bonus: TimeBonus::Increment(secs(30)) },
+        ]);
+        let mut clock = Clock::new(control, Color::White);
+        assert!(!clock.take_back(start));
+
+        press_at(&mut clock, start, &[0, 10, 15]);
+        assert!(clock.take_back(start + secs(30)));
+
+        assert_eq!(clock.turn(), Color::White);
+        assert!(clock.is_running());
+        assert_eq!(clock.move_times(), [secs(120), secs(110)]);
+        assert_eq!(clock.remaining(Color::White, start + secs(31)), secs(119));
+        assert_eq!(clock.remaining(Color::Black, start + secs(31)), secs(110));
+
+        // Taking back the untimed first move leaves the clock stopped.
+        assert!(clock.take_back(start + secs(40)));
+        assert!(clock.take_back(start + secs(40)));
+        assert!(!clock.is_running());
+        assert_eq!(clock.remaining(Color::White, start + secs(50)), secs(90));
+    }
+
+    #[test]
+    fn switch_turn_keeps_both_times() {
+        let start = Instant::now();
+        let mut clock = Clock::new(TimeControl::increment(secs(60), secs(2)), Color::White);
+        press_at(&mut clock, start, &[0, 5]);
+
+        clock.switch_turn(start + secs(8));
+        assert_eq!(clock.turn(), Color::Black);
+        assert!(clock.is_running());
+        assert_eq!(clock.remaining(Color::White, start + secs(10)), secs(59));
+        assert_eq!(clock.remaining(Color::Black, start + secs(10)), secs(55));
+        assert_eq!(clock.move_times(), [secs(62), secs(57)]);
+    }
+}
@@ -0,0 +1,138 @@
+use super::{Board, Color, Game, PieceType, squares};
+
+/// Centipawn value of each piece type, indexed by `PieceType as usize`.
+pub const PIECE_VALUES: [i32; 6] = [0, 900, 500, 330, 320, 100];
+
+const PIECE_TYPES: [PieceType; 6] = [
+    PieceType::King, PieceType::Queen, PieceType::Rook,
+    PieceType::Bishop, PieceType::Knight, PieceType::Pawn,
+];
+
+/// Below this much queen, rook and minor piece material on the board the king
+/// should head for the centre instead of hiding.
+const ENDGAME_MATERIAL: i32 = 1300;
+
+// Piece-square bonuses for White, laid out like the board with the eighth rank
+// first so that a square's index looks up its bonus directly. Black uses the
+// same tables mirrored vertically.
+const PAWN_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+    50, 50, 50, 50, 50, 50, 50, 50,
+    10, 10, 20, 30, 30, 20, 10, 10,
+     5,  5, 10, 25, 25, 10,  5,  5,
+     0,  0,  0, 20, 20,  0,  0,  0,
+     5, -5,-10,  0,  0,-10, -5,  5,
+     5, 10, 10,-20,-20, 10, 10,  5,
+     0,  0,  0,  0,  0,  0,  0,  0,
+];
+
+const KNIGHT_TABLE: [i32; 64] = [
+    -50,-40,-30,-30,-30,-30,-40,-50,
+    -40,-20,  0,  0,  0,  0,-20,-40,
+    -30,  0, 10, 15, 15, 10,  0,-30,
+    -30,  5, 15, 20, 20, 15,  5,-30,
+    -30,  0, 15, 20, 20, 15,  0,-30,
+    -30,  5, 10, 15, 15, 10,  5,-30,
+    -40,-20,  0,  5,  5,  0,-20,-40,
+    -50,-40,-30,-30,-30,-30,-40,-50,
+];
+
+const BISHOP_TABLE: [i32; 64] = [
+    -20,-10,-10,-10,-10,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5, 10, 10,  5,  0,-10,
+    -10,  5,  5, 10, 10,  5,  5,-10,
+    -10,  0, 10, 10, 10, 10,  0,-10,
+    -10, 10, 10, 10, 10, 10, 10,-10,
+    -10,  5,  0,  0,  0,  0,  5,-10,
+    -20,-10,-10,-10,-10,-10,-10,-20,
+];
+
+const ROOK_TABLE: [i32; 64] = [
+     0,  0,  0,  0,  0,  0,  0,  0,
+     5, 10, 10, 10, 10, 10, 10,  5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+    -5,  0,  0,  0,  0,  0,  0, -5,
+     0,  0,  0,  5,  5,  0,  0,  0,
+];
+
+const QUEEN_TABLE: [i32; 64] = [
+    -20,-10,-10, -5, -5,-10,-10,-20,
+    -10,  0,  0,  0,  0,  0,  0,-10,
+    -10,  0,  5,  5,  5,  5,  0,-10,
+     -5,  0,  5,  5,  5,  5,  0, -5,
+      0,  0,  5,  5,  5,  5,  0, -5,
+    -10,  5,  5,  5,  5,  5,  0,-10,
+    -10,  0,  5,  0,  0,  0,  0,-10,
+    -20,-10,-10, -5, -5,-10,-10,-20,
+];
+
+const KING_MIDDLEGAME_TABLE: [i32; 64] = [
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -30,-40,-40,-50,-50,-40,-40,-30,
+    -20,-30,-30,-40,-40,-30,-30,-20,
+    -10,-20,-20,-20,-20,-20,-20,-10,
+     20, 20,  0,  0,  0,  0, 20, 20,
+     20, 30, 10,  0,  0, 10, 30, 20,
+];
+
+const KING_ENDGAME_TABLE: [i32; 64] = [
+    -50,-40,-30,-20,-20,-30,-40,-50,
+    -30,-20,-10,  0,  0,-10,-20,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 30, 40, 40, 30,-10,-30,
+    -30,-10, 20, 30, 30, 20,-10,-30,
+    -30,-30,  0,  0,  0,  0,-30,-30,
+    -50,-30,-30,-30,-30,-30,-30,-50,
+];
+
+impl Game {
+    /// Static evaluation in centipawns from the point of view of the side to
+    /// move: material plus a bonus or penalty for the square each piece is on.
+    pub fn evaluate(&self) -> i32 {
+        let board = self.board();
+        let endgame = non_pawn_material(board) <= ENDGAME_MATERIAL;
+
+        let mut score = 0;
+        for color in [Color::White, Color::Black] {
+            let sign = if color == Color::White { 1 } else { -1 };
+            for piece_type in PIECE_TYPES {
+                let table = piece_square_table(piece_type, endgame);
+                for square in squares(board.pieces(piece_type, color)) {
+                    let table_square = if color == Color::White { square } else { square ^ 56 };
+                    score += sign * (PIECE_VALUES[piece_type as usize] + table[table_square]);
+                }
+            }
+        }
+
+        if self.current_player() == Color::White { score } else { -score }
+    }
+}
+
+fn piece_square_table(piece_type: PieceType, endgame: bool) -> &'static [i32; 64] {
+    match piece_type {
+        PieceType::King if endgame => &KING_ENDGAME_TABLE,
+        PieceType::King => &KING_MIDDLEGAME_TABLE,
+        PieceType::Queen => &QUEEN_TABLE,
+        PieceType::Rook => &ROOK_TABLE,
+        PieceType::Bishop => &BISHOP_TABLE,
+        PieceType::Knight => &KNIGHT_TABLE,
+        PieceType::Pawn => &PAWN_TABLE,
+    }
+}
+
+fn non_pawn_material(board: &Board) -> i32 {
+    [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight]
+        .into_iter()
+        .map(|piece_type| {
+            let count = (board.pieces(piece_type, Color::White) | board.pieces(piece_type, Color::Black)).count_ones();
+            count as i32 * PIECE_VALUES[piece_type as usize]
+        })
+        .sum()
+}
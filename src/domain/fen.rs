@@ -0,0 +1,288 @@
+use std::fmt;
+
+use super::{Board, CastleSide, CastlingRights, Color, Piece, PieceType, Position, king_home};
+
+pub const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FenError {
+    /// A FEN needs 6 space separated fields (the last two may be omitted).
+    WrongFieldCount(usize),
+    WrongRankCount(usize),
+    /// `rank` is the chess rank (8 down to 1) the character was found on.
+    InvalidPiece { rank: usize, found: char },
+    WrongSquareCount { rank: usize, squares: usize },
+    InvalidKingCount { color: Color, count: usize },
+    PawnOnBackRank(Position),
+    InvalidSideToMove(String),
+    InvalidCastling(String),
+    /// A castling right is set but the king or rook isn't on its home square.
+    CastlingRightsMismatch(char),
+    InvalidEnPassant(String),
+    InvalidHalfmoveClock(String),
+    InvalidFullmoveNumber(String),
+    /// The side that just moved is in check, which can't happen in a real game.
+    OpponentInCheck,
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FenError::WrongFieldCount(count) => write!(f, "expected 6 fields, found {}", count),
+            FenError::WrongRankCount(count) => write!(f, "expected 8 ranks, found {}", count),
+            FenError::InvalidPiece { rank, found } => {
+                write!(f, "invalid piece '{}' on rank {}", found, rank)
+            }
+            FenError::WrongSquareCount { rank, squares } => {
+                write!(f, "rank {} describes {} squares instead of 8", rank, squares)
+            }
+            FenError::InvalidKingCount { color, count } => {
+                write!(f, "{:?} has {} kings, expected exactly one", color, count)
+            }
+            FenError::PawnOnBackRank(pos) => write!(f, "pawn on back rank at {}", pos.to_chess_notation()),
+            FenError::InvalidSideToMove(field) => write!(f, "invalid side to move '{}'", field),
+            FenError::InvalidCastling(field) => write!(f, "invalid castling rights '{}'", field),
+            FenError::CastlingRightsMismatch(right) => {
+                write!(f, "castling right '{}' doesn't match the king and rook placement", right)
+            }
+            FenError::InvalidEnPassant(field) => write!(f, "invalid en passant square '{}'", field),
+            FenError::InvalidHalfmoveClock(field) => write!(f, "invalid halfmove clock '{}'", field),
+            FenError::InvalidFullmoveNumber(field) => write!(f, "invalid fullmove number '{}'", field),
+            FenError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl std::error::Error for FenError {}
+
+/// The fields of a FEN record, parsed and checked for consistency.
+pub(super) struct FenFields {
+    pub board: Board,
+    pub side_to_move: Color,
+    pub castling_rights: CastlingRights,
+    pub en_passant_target: Option<Position>,
+    pub halfmove_clock: u32,
+    pub fullmove_number: u32,
+}
+
+impl FenFields {
+    pub fn parse(fen: &str) -> Result<Self, FenError> {
+        let fields: Vec<&str> = fen.split_whitespace().collect();
+        if fields.len() != 4 && fields.len() != 6 {
+            return Err(FenError::WrongFieldCount(fields.len()));
+        }
+
+        let board = Board::from_fen_placement(fields[0])?;
+
+        let side_to_move = match fields[1] {
+            "w" => Color::White,
+            "b" => Color::Black,
+            other => return Err(FenError::InvalidSideToMove(other.to_string())),
+        };
+
+        let castling_rights = parse_castling(fields[2], &board)?;
+        let en_passant_target = parse_en_passant(fields[3], &board, side_to_move)?;
+
+        let (halfmove_clock, fullmove_number) = if fields.len() == 6 {
+            let halfmove = fields[4]
+                .parse()
+                .map_err(|_| FenError::InvalidHalfmoveClock(fields[4].to_string()))?;
+            let fullmove = match fields[5].parse() {
+                Ok(n) if n > 0 => n,
+                _ => return Err(FenError::InvalidFullmoveNumber(fields[5].to_string())),
+            };
+            (halfmove, fullmove)
+        } else {
+            (0, 1)
+        };
+
+        Ok(FenFields {
+            board,
+            side_to_move,
+            castling_rights,
+            en_passant_target,
+            halfmove_clock,
+            fullmove_number,
+        })
+    }
+
+    pub fn to_fen(&self) -> String {
+        let side = match self.side_to_move {
+            Color::White => "w",
+            Color::Black => "b",
+        };
+        let en_passant = self
+            .en_passant_target
+            .map_or("-".to_string(), |pos| pos.to_chess_notation());
+
+        format!(
+            "{} {} {} {} {} {}",
+            self.board.to_fen_placement(),
+            side,
+            castling_to_fen(self.castling_rights),
+            en_passant,
+            self.halfmove_clock,
+            self.fullmove_number
+        )
+    }
+}
+
+impl Board {
+    /// Parses the piece placement field of a FEN, e.g. `rnbqkbnr/pppppppp/8/...`.
+    pub fn from_fen_placement(placement: &str) -> Result<Board, FenError> {
+        let ranks: Vec<&str> = placement.split('/').collect();
+        if ranks.len() != 8 {
+            return Err(FenError::WrongRankCount(ranks.len()));
+        }
+
+        let mut board = Board::empty();
+        for (row, rank_str) in ranks.iter().enumerate() {
+            let rank = 8 - row;
+            let mut col = 0;
+            for c in rank_str.chars() {
+                if let Some(skip) = c.to_digit(10).filter(|d| (1..=8).contains(d)) {
+                    col += skip as usize;
+                    continue;
+                }
+
+                let piece = Piece::from_fen_char(c).ok_or(FenError::InvalidPiece { rank, found: c })?;
+                if col < 8 {
+                    let pos = Position { row, col };
+                    if piece.piece_type == PieceType::Pawn && (row == 0 || row == 7) {
+                        return Err(FenError::PawnOnBackRank(pos));
+                    }
+                    board.set_piece(pos, Some(piece));
+                }
+                col += 1;
+            }
+
+            if col != 8 {
+                return Err(FenError::WrongSquareCount { rank, squares: col });
+            }
+        }
+
+        for color in [Color::White, Color::Black] {
+            let count = board
+                .get_state()
+                .iter()
+                .flatten()
+                .filter(|square| **square == Some(Piece::new(PieceType::King, color)))
+                .count();
+            if count != 1 {
+                return Err(FenError::InvalidKingCount { color, count });
+            }
+        }
+
+        Ok(board)
+    }
+
+    pub fn to_fen_placement(&self) -> String {
+        let mut placement = String::new();
+        for (row, rank) in self.get_state().iter().enumerate() {
+            if row > 0 {
+                placement.push('/');
+            }
+
+            let mut empty = 0;
+            for square in rank {
+                match square {
+                    Some(piece) => {
+                        if empty > 0 {
+                            placement.push_str(&empty.to_string());
+                            empty = 0;
+                        }
+                        placement.push(piece.fen_char());
+                    }
+                    None => empty += 1,
+                }
+            }
+            if empty > 0 {
+                placement.push_str(&empty.to_string());
+            }
+        }
+        placement
+    }
+}
+
+fn parse_castling(field: &str, board: &Board) -> Result<CastlingRights, FenError> {
+    let mut rights = CastlingRights::none();
+    if field == "-" {
+        return Ok(rights);
+    }
+
+    for c in field.chars() {
+        let (color, side) = match c {
+            'K' => (Color::White, CastleSide::KingSide),
+            'Q' => (Color::White, CastleSide::QueenSide),
+            'k' => (Color::Black, CastleSide::KingSide),
+            'q' => (Color::Black, CastleSide::QueenSide),
+            _ => return Err(FenError::InvalidCastling(field.to_string())),
+        };
+
+        if rights.has(color, side) {
+            return Err(FenError::InvalidCastling(field.to_string()));
+        }
+
+        let king_in_place = board.get_piece(king_home(color)) == Some(Piece::new(PieceType::King, color));
+        let rook_in_place = board.get_piece(side.rook_from(color)) == Some(Piece::new(PieceType::Rook, color));
+        if !king_in_place || !rook_in_place {
+            return Err(FenError::CastlingRightsMismatch(c));
+        }
+
+        match (color, side) {
+            (Color::White, CastleSide::KingSide) => rights.white_kingside = true,
+            (Color::White, CastleSide::QueenSide) => rights.white_queenside = true,
+            (Color::Black, CastleSide::KingSide) => rights.black_kingside = true,
+            (Color::Black, CastleSide::QueenSide) => rights.black_queenside = true,
+        }
+    }
+
+    Ok(rights)
+}
+
+fn castling_to_fen(rights: CastlingRights) -> String {
+    let mut field = String::new();
+    if rights.white_kingside {
+        field.push('K');
+    }
+    if rights.white_queenside {
+        field.push('Q');
+    }
+    if rights.black_kingside {
+        field.push('k');
+    }
+    if rights.black_queenside {
+        field.push('q');
+    }
+    if field.is_empty() {
+        field.push('-');
+    }
+    field
+}
+
+/// The target square must sit behind a pawn of the side that just moved, with
+/// both the target and the pawn's starting square empty.
+fn parse_en_passant(field: &str, board: &Board, side_to_move: Color) -> Result<Option<Position>, FenError> {
+    if field == "-" {
+        return Ok(None);
+    }
+
+    let invalid = || FenError::InvalidEnPassant(field.to_string());
+    let target = Position::from_chess_notation(field).ok_or_else(invalid)?;
+
+    let (target_row, pawn_row, start_row) = match side_to_move {
+        Color::White => (2, 3, 1),
+        Color::Black => (5, 4, 6),
+    };
+    let pushed_pawn = Piece::new(PieceType::Pawn, side_to_move.opposite());
+
+    if target.row != target_row
+        || board.get_piece(Position { row: pawn_row, col: target.col }) != Some(pushed_pawn)
+        || board.get_piece(target).is_some()
+        || board.get_piece(Position { row: start_row, col: target.col }).is_some()
+    {
+        return Err(invalid());
+    }
+
+    Ok(Some(target))
+}
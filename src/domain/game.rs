@@ -1,4 +1,4 @@
-use super::{Board, Position, Color, PieceType, MoveRecord};
+use super::{Board, Position, Color, Piece, PieceType, MoveRecord, GameStatus, DrawReason, CastleSide, CastlingRights, Move, MoveError, FenError, FenFields, king_home};
 
 #[derive(Debug, Clone)]
 pub struct Game {
@@ -6,16 +6,80 @@ pub struct Game {
     current_player: Color,
     move_history: Vec<MoveRecord>,
     history_index: usize,
+    status: GameStatus,
+    castling_rights: CastlingRights,
+    en_passant_target: Option<Position>,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    /// Zobrist key of the position, updated by every move made or taken back.
+    zobrist_key: u64,
+    /// Keys of every position reached so far, starting with the initial one,
+    /// used to detect repetitions.
+    position_history: Vec<u64>,
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Game::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Self {
-        Game {
+        let mut game = Game {
             board: Board::new(),
             current_player: Color::White,
             move_history: Vec::new(),
             history_index: 0,
+            status: GameStatus::Ongoing,
+            castling_rights: CastlingRights::all(),
+            en_passant_target: None,
+            halfmove_clock: 0,
+            fullmove_number: 1,
+            zobrist_key: 0,
+            position_history: Vec::new(),
+        };
+        game.zobrist_key = game.compute_zobrist_key();
+        game.record_position();
+        game
+    }
+
+    pub fn from_fen(fen: &str) -> Result<Game, FenError> {
+        let fields = FenFields::parse(fen)?;
+        let mut game = Game {
+            board: fields.board,
+            current_player: fields.side_to_move,
+            move_history: Vec::new(),
+            history_index: 0,
+            status: GameStatus::Ongoing,
+            castling_rights: fields.castling_rights,
+            en_passant_target: fields.en_passant_target,
+            halfmove_clock: fields.halfmove_clock,
+            fullmove_number: fields.fullmove_number,
+            zobrist_key: 0,
+            position_history: Vec::new(),
+        };
+        game.zobrist_key = game.compute_zobrist_key();
+
+        if game.is_in_check(game.current_player.opposite()) {
+            return Err(FenError::OpponentInCheck);
+        }
+
+        game.record_position();
+        game.update_status();
+        Ok(game)
+    }
+
+    pub fn to_fen(&self) -> String {
+        FenFields {
+            board: self.board.clone(),
+            side_to_move: self.current_player,
+            castling_rights: self.castling_rights,
+            en_passant_target: self.en_passant_target,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
         }
+        .to_fen()
     }
 
     pub fn board(&self) -> &Board {
@@ -26,50 +90,238 @@ impl Game {
         self.current_player
     }
 
-    pub fn make_move(&mut self, from: Position, to: Position) -> Result<(), String> {
-        let piece = self.board.get_piece(from)
-            .ok_or("No piece at source position")?;
+    pub fn status(&self) -> GameStatus {
+        self.status
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        self.castling_rights
+    }
+
+    /// Square a pawn skipped over with a double push on the previous move,
+    /// i.e. where an en passant capture would land.
+    pub fn en_passant_target(&self) -> Option<Position> {
+        self.en_passant_target
+    }
+
+    /// Zobrist key of the position: the pieces, side to move, castling rights
+    /// and en passant file. The file only counts when a pawn of the side to
+    /// move stands next to the pawn that just advanced two squares, so
+    /// positions that merely differ in an unusable en passant target share a
+    /// key.
+    pub fn zobrist_key(&self) -> u64 {
+        self.zobrist_key
+    }
+
+    /// Moves leading to the current position, oldest first. Moves that were
+    /// undone (and could be redone) are not included.
+    pub fn move_history(&self) -> &[MoveRecord] {
+        &self.move_history[..self.history_index]
+    }
+
+    /// The whole recorded line, including moves that were undone and can be
+    /// redone.
+    pub fn recorded_moves(&self) -> &[MoveRecord] {
+        &self.move_history
+    }
+
+    /// How many moves of `recorded_moves` are on the board.
+    pub fn current_ply(&self) -> usize {
+        self.history_index
+    }
+
+    /// Undoes or redoes moves until `ply` moves of the recorded line are on
+    /// the board. Returns false if the line is shorter than that.
+    pub fn go_to_ply(&mut self, ply: usize) -> bool {
+        if ply > self.move_history.len() {
+            return false;
+        }
+        while self.history_index > ply {
+            self.undo_move();
+        }
+        while self.history_index < ply {
+            self.redo_move();
+        }
+        true
+    }
+
+    /// The move that led to the current position, if any.
+    pub fn last_move(&self) -> Option<&MoveRecord> {
+        self.move_history().last()
+    }
+
+    /// Halfmoves since the last capture or pawn move.
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    /// Starts at 1 and is incremented after each Black move.
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    pub fn is_in_check(&self, color: Color) -> bool {
+        match self.board.find_king(color) {
+            Some(king_pos) => self.board.is_square_attacked(king_pos, color.opposite()),
+            None => false,
+        }
+    }
+
+    /// Returns true if moving the piece on `from` to `to` is a pawn reaching the
+    /// last rank, i.e. the move needs a promotion piece.
+    pub fn is_promotion_move(&self, from: Position, to: Position) -> bool {
+        match self.board.get_piece(from) {
+            Some(piece) if piece.piece_type == PieceType::Pawn => {
+                let last_row = match piece.color {
+                    Color::White => 0,
+                    Color::Black => 7,
+                };
+                to.row == last_row
+            }
+            _ => false,
+        }
+    }
+
+    /// Every fully legal move for the side to move, with one entry per
+    /// promotion piece. Empty once the game is over.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.status.is_game_over() {
+            return Vec::new();
+        }
+        self.generate_legal_moves()
+    }
+
+    /// Legal moves of the piece on `from`; empty if it doesn't belong to the
+    /// side to move.
+    pub fn legal_moves_from(&self, from: Position) -> Vec<Move> {
+        if self.status.is_game_over() {
+            return Vec::new();
+        }
+        self.generate_legal_moves_from(from)
+    }
+
+    /// Squares the piece on `from` can legally move to, each listed once even
+    /// when several promotion pieces lead there.
+    pub fn legal_destinations(&self, from: Position) -> Vec<Position> {
+        let mut destinations = Vec::new();
+        for mv in self.legal_moves_from(from) {
+            if !destinations.contains(&mv.to) {
+                destinations.push(mv.to);
+            }
+        }
+        destinations
+    }
+
+    pub fn make_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        let Move { from, to, promotion } = mv;
+
+        if self.status.is_game_over() {
+            return Err(MoveError::GameOver);
+        }
+
+        let piece = self.board.get_piece(from).ok_or(MoveError::NoPieceAtSource)?;
 
         if piece.color != self.current_player {
-            return Err("Not your piece".to_string());
+            return Err(MoveError::NotYourTurn);
         }
 
-        if !self.is_valid_move(from, to) {
-            return Err("Invalid move".to_string());
+        self.validate_move(from, to)?;
+
+        match (self.is_promotion_move(from, to), promotion) {
+            (true, None) => return Err(MoveError::PromotionRequired),
+            (true, Some(PieceType::Queen | PieceType::Rook | PieceType::Bishop | PieceType::Knight)) => {}
+            (true, Some(_)) => return Err(MoveError::InvalidPromotionPiece),
+            (false, Some(_)) => return Err(MoveError::UnexpectedPromotion),
+            (false, None) => {}
         }
 
-        let captured_piece = self.board.move_piece(from, to);
-        
-        let move_record = MoveRecord::new(from, to, piece, captured_piece);
-        
+        if self.leaves_king_in_check(from, to) {
+            return Err(MoveError::LeavesKingInCheck);
+        }
+
+        self.play_unchecked(mv);
+        Ok(())
+    }
+
+    /// Plays a move already known to be legal, even if the game is over.
+    pub(super) fn play_unchecked(&mut self, mv: Move) {
+        self.push_move(mv);
+        self.record_position();
+        self.update_status();
+    }
+
+    /// Plays a legal move without updating the status or the repetition
+    /// history, for perft and search, which undo it again with `pop_move`.
+    pub(super) fn push_move(&mut self, mv: Move) {
+        let Some(piece) = self.board.get_piece(mv.from) else {
+            return;
+        };
+
+        let move_record = self.build_move_record(mv, piece);
+        self.apply_move(&move_record);
+
         self.move_history.truncate(self.history_index);
         self.move_history.push(move_record);
         self.history_index += 1;
-        
-        self.current_player = match self.current_player {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
+    }
 
-        Ok(())
+    /// Plays a move given in UCI notation (`e2e4`, `e7e8q`, `e1g1`), with
+    /// the same validation as `make_move`.
+    pub fn apply_uci_move(&mut self, uci: &str) -> Result<(), MoveError> {
+        let mv = Move::from_uci(uci).ok_or_else(|| MoveError::InvalidNotation(uci.to_string()))?;
+        self.make_move(mv)
+    }
+
+    /// UCI notation of every move in `move_history`, ready to be joined into
+    /// a `position ... moves ...` command.
+    pub fn uci_history(&self) -> Vec<String> {
+        self.move_history().iter().map(MoveRecord::to_uci).collect()
     }
 
     pub fn undo_move(&mut self) -> bool {
+        if !self.pop_move() {
+            return false;
+        }
+
+        self.position_history.truncate(self.history_index + 1);
+        self.update_status();
+        true
+    }
+
+    /// Takes back the last move on the board and in the counters, leaving the
+    /// status and repetition history to the caller.
+    pub(super) fn pop_move(&mut self) -> bool {
         if self.history_index == 0 {
             return false;
         }
 
         self.history_index -= 1;
         let move_record = &self.move_history[self.history_index];
+        let old_key = self.board.zobrist_key() ^ self.state_key();
 
         self.board.set_piece(move_record.from, Some(move_record.moved_piece));
-        self.board.set_piece(move_record.to, move_record.captured_piece);
-
-        self.current_player = match self.current_player {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
+        match move_record.en_passant_capture {
+            Some(captured_pos) => {
+                self.board.set_piece(move_record.to, None);
+                self.board.set_piece(captured_pos, move_record.captured_piece);
+            }
+            None => {
+                self.board.set_piece(move_record.to, move_record.captured_piece);
+            }
+        }
+        if let Some((rook_from, rook_to)) = move_record.rook_move {
+            self.board.move_piece(rook_to, rook_from);
+        }
+        self.castling_rights = move_record.previous_castling_rights;
+        self.en_passant_target = move_record.previous_en_passant_target;
+        self.halfmove_clock = move_record.previous_halfmove_clock;
+        if move_record.moved_piece.color == Color::Black {
+            self.fullmove_number -= 1;
+        }
 
+        self.current_player = self.current_player.opposite();
+        self.zobrist_key ^= old_key ^ self.board.zobrist_key() ^ self.state_key();
+        debug_assert_eq!(self.zobrist_key, self.compute_zobrist_key(), "incremental Zobrist key out of sync");
         true
     }
 
@@ -78,15 +330,12 @@ impl Game {
             return false;
         }
 
-        let move_record = &self.move_history[self.history_index];
-        self.board.move_piece(move_record.from, move_record.to);
-        
+        let move_record = self.move_history[self.history_index].clone();
+        self.apply_move(&move_record);
+
         self.history_index += 1;
-        
-        self.current_player = match self.current_player {
-            Color::White => Color::Black,
-            Color::Black => Color::White,
-        };
+        self.record_position();
+        self.update_status();
 
         true
     }
@@ -96,35 +345,258 @@ impl Game {
         self.current_player = Color::White;
         self.move_history.clear();
         self.history_index = 0;
+        self.status = GameStatus::Ongoing;
+        self.castling_rights = CastlingRights::all();
+        self.en_passant_target = None;
+        self.halfmove_clock = 0;
+        self.fullmove_number = 1;
+        self.zobrist_key = self.compute_zobrist_key();
+        self.position_history.clear();
+        self.record_position();
     }
 
-    fn is_valid_move(&self, from: Position, to: Position) -> bool {
-        if from == to {
-            return false;
+    /// How many times the current position has occurred, counting this one.
+    pub fn repetition_count(&self) -> usize {
+        match self.position_history.last() {
+            Some(current) => self.position_history.iter().filter(|key| *key == current).count(),
+            None => 0,
+        }
+    }
+
+    /// The draw the side to move may claim in the current position, if any.
+    pub fn claimable_draw(&self) -> Option<DrawReason> {
+        if self.status.is_game_over() {
+            None
+        } else if self.repetition_count() >= 3 {
+            Some(DrawReason::ThreefoldRepetition)
+        } else if self.halfmove_clock >= 100 {
+            Some(DrawReason::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    /// Ends the game as a draw if `claimable_draw` allows it. Undoing a move
+    /// withdraws the claim.
+    pub fn claim_draw(&mut self) -> Result<DrawReason, MoveError> {
+        let reason = self.claimable_draw().ok_or(MoveError::NoDrawToClaim)?;
+        self.status = GameStatus::Draw(reason);
+        Ok(reason)
+    }
+
+    /// Ends the game because `color` ran out of time: a loss, or a draw when
+    /// the opponent couldn't have mated them anyway. Like a draw claim, it is
+    /// withdrawn by undoing a move.
+    pub fn lose_on_time(&mut self, color: Color) {
+        if self.status.is_game_over() {
+            return;
+        }
+
+        self.status = if self.board.has_mating_material(color.opposite()) {
+            GameStatus::TimeForfeit(color.opposite())
+        } else {
+            GameStatus::Draw(DrawReason::TimeoutVsInsufficientMaterial)
+        };
+    }
+
+    /// Repetition keys of every position since the start, the current one last.
+    pub(super) fn position_keys(&self) -> &[u64] {
+        &self.position_history
+    }
+
+    /// Appends the current position to `position_history`, dropping entries
+    /// of moves that were undone.
+    fn record_position(&mut self) {
+        self.position_history.truncate(self.history_index);
+        self.position_history.push(self.position_key());
+    }
+
+    /// Positions are equal for repetition purposes when the pieces, side to
+    /// move, castling rights and available en passant captures all match. The
+    /// Zobrist key counts an en passant file whenever a pawn stands ready to
+    /// capture, so it is taken out again when that capture would be illegal.
+    /// The search keys the positions it visits with this as well, so that
+    /// they compare equal to the game's.
+    pub(super) fn position_key(&self) -> u64 {
+        if self.capturable_en_passant_target().is_some() {
+            self.zobrist_key()
+        } else {
+            self.zobrist_key() ^ self.en_passant_key()
         }
+    }
+
+    /// The en passant target, but only if a pawn can legally capture onto it.
+    fn capturable_en_passant_target(&self) -> Option<Position> {
+        let target = self.en_passant_target?;
+        let capturer_row = match self.current_player {
+            Color::White => target.row + 1,
+            Color::Black => target.row - 1,
+        };
+
+        let can_capture = [target.col.checked_sub(1), Some(target.col + 1)]
+            .into_iter()
+            .flatten()
+            .filter_map(|col| Position::new(capturer_row, col))
+            .filter(|&pos| self.board.get_piece(pos) == Some(Piece::new(PieceType::Pawn, self.current_player)))
+            .any(|pos| self.generate_legal_moves_from(pos).iter().any(|mv| mv.to == target));
+
+        if can_capture { Some(target) } else { None }
+    }
+
+    fn build_move_record(&self, mv: Move, piece: Piece) -> MoveRecord {
+        let Move { from, to, promotion } = mv;
+        let mut move_record = MoveRecord::new(
+            from,
+            to,
+            piece,
+            self.board.get_piece(to),
+            self.castling_rights,
+            self.en_passant_target,
+            self.halfmove_clock,
+        );
+        move_record.promotion = promotion;
+        move_record.rook_move = Self::castling_rook_move(from, to, piece);
+
+        if piece.piece_type == PieceType::Pawn && Some(to) == self.en_passant_target && from.col != to.col {
+            let captured_pos = Position { row: from.row, col: to.col };
+            move_record.captured_piece = self.board.get_piece(captured_pos);
+            move_record.en_passant_capture = Some(captured_pos);
+        }
+
+        move_record
+    }
+
+    /// Moves the pieces described by `move_record` on `board`, including the rook
+    /// when castling, the captured pawn when taking en passant and the piece
+    /// swap when promoting.
+    fn apply_to_board(board: &mut Board, move_record: &MoveRecord) {
+        board.move_piece(move_record.from, move_record.to);
+        if let Some(promotion) = move_record.promotion {
+            board.set_piece(move_record.to, Some(Piece::new(promotion, move_record.moved_piece.color)));
+        }
+        if let Some(captured_pos) = move_record.en_passant_capture {
+            board.set_piece(captured_pos, None);
+        }
+        if let Some((rook_from, rook_to)) = move_record.rook_move {
+            board.move_piece(rook_from, rook_to);
+        }
+    }
 
+    /// Applies `move_record` to the board, updates castling rights, the en
+    /// passant target, the move counters and the Zobrist key, and passes the
+    /// turn. Shared by `make_move` and `redo_move`.
+    fn apply_move(&mut self, move_record: &MoveRecord) {
+        let old_key = self.board.zobrist_key() ^ self.state_key();
+        Self::apply_to_board(&mut self.board, move_record);
+
+        self.castling_rights.update_for_square(move_record.from);
+        self.castling_rights.update_for_square(move_record.to);
+
+        let is_double_push = move_record.moved_piece.piece_type == PieceType::Pawn
+            && move_record.from.row.abs_diff(move_record.to.row) == 2;
+        self.en_passant_target = if is_double_push {
+            Position::new((move_record.from.row + move_record.to.row) / 2, move_record.from.col)
+        } else {
+            None
+        };
+
+        if move_record.moved_piece.piece_type == PieceType::Pawn || move_record.captured_piece.is_some() {
+            self.halfmove_clock = 0;
+        } else {
+            self.halfmove_clock += 1;
+        }
+        if move_record.moved_piece.color == Color::Black {
+            self.fullmove_number += 1;
+        }
+
+        self.current_player = self.current_player.opposite();
+        self.zobrist_key ^= old_key ^ self.board.zobrist_key() ^ self.state_key();
+        debug_assert_eq!(self.zobrist_key, self.compute_zobrist_key(), "incremental Zobrist key out of sync");
+    }
+
+    fn castling_rook_move(from: Position, to: Position, piece: Piece) -> Option<(Position, Position)> {
+        if piece.piece_type != PieceType::King || from.row != to.row || from.col.abs_diff(to.col) != 2 {
+            return None;
+        }
+
+        let color = piece.color;
+        let side = if to.col > from.col { CastleSide::KingSide } else { CastleSide::QueenSide };
+        Some((side.rook_from(color), side.rook_to(color)))
+    }
+
+    fn update_status(&mut self) {
+        let in_check = self.is_in_check(self.current_player);
+        let has_legal_move = self.has_legal_move();
+
+        self.status = if !has_legal_move {
+            if in_check {
+                GameStatus::Checkmate(self.current_player.opposite())
+            } else {
+                GameStatus::Draw(DrawReason::Stalemate)
+            }
+        } else if self.board.has_insufficient_material() {
+            GameStatus::Draw(DrawReason::InsufficientMaterial)
+        } else if self.repetition_count() >= 5 {
+            GameStatus::Draw(DrawReason::FivefoldRepetition)
+        } else if self.halfmove_clock >= 150 {
+            GameStatus::Draw(DrawReason::SeventyFiveMoveRule)
+        } else if in_check {
+            GameStatus::Check
+        } else {
+            GameStatus::Ongoing
+        };
+    }
+
+    pub(super) fn has_legal_move(&self) -> bool {
+        !self.generate_legal_moves().is_empty()
+    }
+
+    fn generate_legal_moves_from(&self, from: Position) -> Vec<Move> {
+        self.generate_legal_moves().into_iter().filter(|mv| mv.from == from).collect()
+    }
+
+    fn leaves_king_in_check(&self, from: Position, to: Position) -> bool {
         let piece = match self.board.get_piece(from) {
             Some(p) => p,
             None => return false,
         };
+        let color = piece.color;
 
-        if let Some(target_piece) = self.board.get_piece(to) {
-            if target_piece.color == piece.color {
-                return false;
-            }
+        let mut board = self.board.clone();
+        Self::apply_to_board(&mut board, &self.build_move_record(Move::new(from, to), piece));
+
+        match board.find_king(color) {
+            Some(king_pos) => board.is_square_attacked(king_pos, color.opposite()),
+            None => false,
+        }
+    }
+
+    /// Checks that the piece on `from` may move to `to` by its movement rules,
+    /// without looking at whether the own king ends up in check.
+    fn validate_move(&self, from: Position, to: Position) -> Result<(), MoveError> {
+        if from == to {
+            return Err(MoveError::IllegalGeometry);
+        }
+
+        let piece = self.board.get_piece(from).ok_or(MoveError::NoPieceAtSource)?;
+
+        if let Some(target_piece) = self.board.get_piece(to)
+            && target_piece.color == piece.color
+        {
+            return Err(MoveError::OwnPieceAtDestination);
         }
 
         match piece.piece_type {
-            PieceType::Pawn => self.is_valid_pawn_move(from, to, piece.color),
-            PieceType::Rook => self.is_valid_rook_move(from, to),
-            PieceType::Bishop => self.is_valid_bishop_move(from, to),
-            PieceType::Queen => self.is_valid_queen_move(from, to),
-            PieceType::King => self.is_valid_king_move(from, to),
-            PieceType::Knight => self.is_valid_knight_move(from, to),
+            PieceType::Pawn => self.validate_pawn_move(from, to, piece.color),
+            PieceType::Rook => self.validate_rook_move(from, to),
+            PieceType::Bishop => self.validate_bishop_move(from, to),
+            PieceType::Queen => self.validate_queen_move(from, to),
+            PieceType::King => self.validate_king_move(from, to, piece.color),
+            PieceType::Knight => self.validate_knight_move(from, to),
         }
     }
 
-    fn is_valid_pawn_move(&self, from: Position, to: Position, color: Color) -> bool {
+    fn validate_pawn_move(&self, from: Position, to: Position, color: Color) -> Result<(), MoveError> {
         let direction = match color {
             Color::White => -1,
             Color::Black => 1,
@@ -133,88 +605,175 @@ impl Game {
         let row_diff = to.row as i32 - from.row as i32;
         let col_diff = to.col as i32 - from.col as i32;
 
-        if col_diff == 0 {
-            if row_diff == direction && self.board.get_piece(to).is_none() {
-                return true;
-            }
-            
-            let start_row = match color {
-                Color::White => 6,
-                Color::Black => 1,
-            };
-            
-            if from.row == start_row && row_diff == direction * 2 && self.board.get_piece(to).is_none() {
-                return true;
+        let start_row = match color {
+            Color::White => 6,
+            Color::Black => 1,
+        };
+
+        if col_diff == 0 && (row_diff == direction || (from.row == start_row && row_diff == direction * 2)) {
+            if self.board.get_piece(to).is_some() || !self.board.is_path_clear(from, to) {
+                return Err(MoveError::PathBlocked);
             }
-        } else if col_diff.abs() == 1 && row_diff == direction {
-            return self.board.get_piece(to).is_some();
+            return Ok(());
         }
 
-        false
+        if col_diff.abs() == 1
+            && row_diff == direction
+            && (self.board.get_piece(to).is_some() || self.en_passant_target == Some(to))
+        {
+            return Ok(());
+        }
+
+        Err(MoveError::IllegalGeometry)
     }
 
-    fn is_valid_rook_move(&self, from: Position, to: Position) -> bool {
+    fn validate_rook_move(&self, from: Position, to: Position) -> Result<(), MoveError> {
         if from.row != to.row && from.col != to.col {
-            return false;
+            return Err(MoveError::IllegalGeometry);
         }
-        self.is_path_clear(from, to)
+        self.validate_path(from, to)
     }
 
-    fn is_valid_bishop_move(&self, from: Position, to: Position) -> bool {
+    fn validate_bishop_move(&self, from: Position, to: Position) -> Result<(), MoveError> {
         let row_diff = (to.row as i32 - from.row as i32).abs();
         let col_diff = (to.col as i32 - from.col as i32).abs();
         
         if row_diff != col_diff {
-            return false;
+            return Err(MoveError::IllegalGeometry);
         }
         
-        self.is_path_clear(from, to)
+        self.validate_path(from, to)
     }
 
-    fn is_valid_queen_move(&self, from: Position, to: Position) -> bool {
-        self.is_valid_rook_move(from, to) || self.is_valid_bishop_move(from, to)
+    fn validate_queen_move(&self, from: Position, to: Position) -> Result<(), MoveError> {
+        if from.row == to.row || from.col == to.col {
+            self.validate_rook_move(from, to)
+        } else {
+            self.validate_bishop_move(from, to)
+        }
     }
 
-    fn is_valid_king_move(&self, from: Position, to: Position) -> bool {
+    fn validate_king_move(&self, from: Position, to: Position, color: Color) -> Result<(), MoveError> {
         let row_diff = (to.row as i32 - from.row as i32).abs();
         let col_diff = (to.col as i32 - from.col as i32).abs();
         
-        row_diff <= 1 && col_diff <= 1
+        if row_diff <= 1 && col_diff <= 1 {
+            return Ok(());
+        }
+
+        self.validate_castling(from, to, color)
+    }
+
+    fn validate_castling(&self, from: Position, to: Position, color: Color) -> Result<(), MoveError> {
+        if from != king_home(color) {
+            return Err(MoveError::IllegalGeometry);
+        }
+
+        let side = if to == CastleSide::KingSide.king_to(color) {
+            CastleSide::KingSide
+        } else if to == CastleSide::QueenSide.king_to(color) {
+            CastleSide::QueenSide
+        } else {
+            return Err(MoveError::IllegalGeometry);
+        };
+
+        if !self.castling_rights.has(color, side) {
+            return Err(MoveError::CastlingRightsLost);
+        }
+
+        let rook_from = side.rook_from(color);
+        match self.board.get_piece(rook_from) {
+            Some(rook) if rook.piece_type == PieceType::Rook && rook.color == color => {}
+            _ => return Err(MoveError::CastlingRightsLost),
+        }
+
+        self.validate_path(from, rook_from)?;
+
+        // The king may not castle out of check or through an attacked square;
+        // landing in check is rejected by `leaves_king_in_check` like any other move.
+        let opponent = color.opposite();
+        if self.board.is_square_attacked(from, opponent) {
+            return Err(MoveError::CastlingOutOfCheck);
+        }
+        if self.board.is_square_attacked(side.rook_to(color), opponent) {
+            return Err(MoveError::CastlingThroughCheck);
+        }
+
+        Ok(())
     }
 
-    fn is_valid_knight_move(&self, from: Position, to: Position) -> bool {
+    fn validate_knight_move(&self, from: Position, to: Position) -> Result<(), MoveError> {
         let row_diff = (to.row as i32 - from.row as i32).abs();
         let col_diff = (to.col as i32 - from.col as i32).abs();
         
-        (row_diff == 2 && col_diff == 1) || (row_diff == 1 && col_diff == 2)
+        if (row_diff == 2 && col_diff == 1) || (row_diff == 1 && col_diff == 2) {
+            Ok(())
+        } else {
+            Err(MoveError::IllegalGeometry)
+        }
     }
 
-    fn is_path_clear(&self, from: Position, to: Position) -> bool {
-        let row_step = match to.row.cmp(&from.row) {
-            std::cmp::Ordering::Greater => 1,
-            std::cmp::Ordering::Less => -1,
-            std::cmp::Ordering::Equal => 0,
-        };
-        
-        let col_step = match to.col.cmp(&from.col) {
-            std::cmp::Ordering::Greater => 1,
-            std::cmp::Ordering::Less => -1,
-            std::cmp::Ordering::Equal => 0,
-        };
+    fn validate_path(&self, from: Position, to: Position) -> Result<(), MoveError> {
+        if self.board.is_path_clear(from, to) {
+            Ok(())
+        } else {
+            Err(MoveError::PathBlocked)
+        }
+    }
+}
 
-        let mut current_row = from.row as i32 + row_step;
-        let mut current_col = from.col as i32 + col_step;
-        
-        while current_row != to.row as i32 || current_col != to.col as i32 {
-            let pos = Position::new(current_row as usize, current_col as usize).unwrap();
-            if self.board.get_piece(pos).is_some() {
-                return false;
-            }
-            
-            current_row += row_step;
-            current_col += col_step;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            game.apply_uci_move(uci).unwrap();
         }
-        
-        true
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn fifty_move_rule_is_claimable_from_the_hundredth_halfmove() {
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w - - 99 60").unwrap();
+        assert_eq!(game.claimable_draw(), None);
+
+        play(&mut game, &["a1a2"]);
+        assert_eq!(game.halfmove_clock(), 100);
+        assert_eq!(game.claimable_draw(), Some(DrawReason::FiftyMoveRule));
+        assert_eq!(game.status(), GameStatus::Ongoing);
+    }
+
+    #[test]
+    fn checkmate_on_the_hundredth_halfmove_stands() {
+        let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 99 60").unwrap();
+        play(&mut game, &["a1a8"]);
+        assert_eq!(game.status(), GameStatus::Checkmate(Color::White));
+        assert_eq!(game.claimable_draw(), None);
+    }
+
+    #[test]
+    fn threefold_repetition_is_claimable_and_fivefold_is_automatic() {
+        let shuffle = ["g1f3", "g8f6", "f3g1", "f6g8"];
+        let mut game = Game::new();
+        play(&mut game, &shuffle);
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 3);
+        assert_eq!(game.claimable_draw(), Some(DrawReason::ThreefoldRepetition));
+        assert_eq!(game.status(), GameStatus::Ongoing);
+
+        play(&mut game, &shuffle);
+        assert_eq!(game.status(), GameStatus::Ongoing);
+        play(&mut game, &shuffle);
+        assert_eq!(game.repetition_count(), 5);
+        assert_eq!(game.status(), GameStatus::Draw(DrawReason::FivefoldRepetition));
+    }
+
+    #[test]
+    fn insufficient_material_ends_the_game() {
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap();
+        assert_eq!(game.status(), GameStatus::Draw(DrawReason::InsufficientMaterial));
+
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/1N2KN2 w - - 0 1").unwrap();
+        assert_eq!(game.status(), GameStatus::Ongoing);
+    }
+}
@@ -0,0 +1,49 @@
+use super::Color;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DrawReason {
+    Stalemate,
+    /// Neither side has enough material left to deliver mate.
+    InsufficientMaterial,
+    /// Claimed by a player after 50 moves without a capture or pawn move.
+    FiftyMoveRule,
+    /// Automatic after 75 moves without a capture or pawn move.
+    SeventyFiveMoveRule,
+    /// Claimed by a player when the position occurred for the third time.
+    ThreefoldRepetition,
+    /// Automatic when the position occurs for the fifth time.
+    FivefoldRepetition,
+    /// A player ran out of time but their opponent couldn't have mated them.
+    TimeoutVsInsufficientMaterial,
+}
+
+impl DrawReason {
+    pub fn description(&self) -> &'static str {
+        match self {
+            DrawReason::Stalemate => "stalemate",
+            DrawReason::InsufficientMaterial => "insufficient material",
+            DrawReason::FiftyMoveRule => "fifty-move rule",
+            DrawReason::SeventyFiveMoveRule => "seventy-five-move rule",
+            DrawReason::ThreefoldRepetition => "threefold repetition",
+            DrawReason::FivefoldRepetition => "fivefold repetition",
+            DrawReason::TimeoutVsInsufficientMaterial => "timeout vs insufficient material",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GameStatus {
+    Ongoing,
+    Check,
+    /// Holds the winning color.
+    Checkmate(Color),
+    /// The other player ran out of time. Holds the winning color.
+    TimeForfeit(Color),
+    Draw(DrawReason),
+}
+
+impl GameStatus {
+    pub fn is_game_over(&self) -> bool {
+        matches!(self, GameStatus::Checkmate(_) | GameStatus::TimeForfeit(_) | GameStatus::Draw(_))
+    }
+}
@@ -1,11 +1,36 @@
 pub mod piece;
 pub mod position;
+pub mod bitboard;
 pub mod board;
 pub mod game;
 pub mod move_record;
+pub mod game_status;
+pub mod castling;
+pub mod chess_move;
+pub mod move_error;
+pub mod fen;
+pub mod san;
+pub mod pgn;
+pub mod movegen;
+pub mod perft;
+pub mod evaluation;
+pub mod search;
+pub mod zobrist;
+pub mod clock;
 
 pub use piece::*;
 pub use position::*;
+pub use bitboard::*;
 pub use board::*;
 pub use game::*;
-pub use move_record::*;
\ No newline at end of file
+pub use move_record::*;
+pub use game_status::*;
+pub use castling::*;
+pub use chess_move::*;
+pub use move_error::*;
+pub use fen::*;
+pub use san::*;
+pub use pgn::*;
+pub use evaluation::*;
+pub use search::*;
+pub use clock::*;
@@ -0,0 +1,52 @@
+use std::fmt;
+
+/// Why a move or game action was rejected.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MoveError {
+    GameOver,
+    NoPieceAtSource,
+    NotYourTurn,
+    /// The destination holds a piece of the mover's own color.
+    OwnPieceAtDestination,
+    /// The piece can't move in that pattern (or a pawn has nothing to capture).
+    IllegalGeometry,
+    PathBlocked,
+    LeavesKingInCheck,
+    CastlingRightsLost,
+    CastlingOutOfCheck,
+    CastlingThroughCheck,
+    PromotionRequired,
+    /// Promotion to a king or pawn was requested.
+    InvalidPromotionPiece,
+    /// A promotion piece was given for a move that doesn't promote.
+    UnexpectedPromotion,
+    /// A move in text form (UCI or SAN) couldn't be read.
+    InvalidNotation(String),
+    NoDrawToClaim,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MoveError::GameOver => write!(f, "the game is over"),
+            MoveError::NoPieceAtSource => write!(f, "there is no piece on the source square"),
+            MoveError::NotYourTurn => write!(f, "that piece belongs to the other player"),
+            MoveError::OwnPieceAtDestination => write!(f, "the destination is occupied by your own piece"),
+            MoveError::IllegalGeometry => write!(f, "that piece can't move that way"),
+            MoveError::PathBlocked => write!(f, "the path is blocked"),
+            MoveError::LeavesKingInCheck => write!(f, "the move would leave your king in check"),
+            MoveError::CastlingRightsLost => write!(f, "castling on that side is no longer allowed"),
+            MoveError::CastlingOutOfCheck => write!(f, "you can't castle out of check"),
+            MoveError::CastlingThroughCheck => write!(f, "the king can't castle through an attacked square"),
+            MoveError::PromotionRequired => write!(f, "choose a piece to promote to"),
+            MoveError::InvalidPromotionPiece => {
+                write!(f, "pawns can only promote to a queen, rook, bishop or knight")
+            }
+            MoveError::UnexpectedPromotion => write!(f, "only pawns reaching the last rank can promote"),
+            MoveError::InvalidNotation(text) => write!(f, "'{}' is not a valid move", text),
+            MoveError::NoDrawToClaim => write!(f, "no draw can be claimed in this position"),
+        }
+    }
+}
+
+impl std::error::Error for MoveError {}
@@ -1,4 +1,4 @@
-use super::{Position, Piece};
+use super::{Position, Piece, PieceType, CastlingRights, Move};
 
 #[derive(Debug, Clone)]
 pub struct MoveRecord {
@@ -6,15 +6,57 @@ pub struct MoveRecord {
     pub to: Position,
     pub moved_piece: Piece,
     pub captured_piece: Option<Piece>,
+    pub promotion: Option<PieceType>,
+    /// Rook relocation `(from, to)` when the move is a castling move.
+    pub rook_move: Option<(Position, Position)>,
+    /// Square of the pawn taken en passant, which differs from `to`.
+    pub en_passant_capture: Option<Position>,
+    pub previous_castling_rights: CastlingRights,
+    pub previous_en_passant_target: Option<Position>,
+    pub previous_halfmove_clock: u32,
 }
 
 impl MoveRecord {
-    pub fn new(from: Position, to: Position, moved_piece: Piece, captured_piece: Option<Piece>) -> Self {
+    pub fn new(
+        from: Position,
+        to: Position,
+        moved_piece: Piece,
+        captured_piece: Option<Piece>,
+        previous_castling_rights: CastlingRights,
+        previous_en_passant_target: Option<Position>,
+        previous_halfmove_clock: u32,
+    ) -> Self {
         MoveRecord {
             from,
             to,
             moved_piece,
             captured_piece,
+            promotion: None,
+            rook_move: None,
+            en_passant_capture: None,
+            previous_castling_rights,
+            previous_en_passant_target,
+            previous_halfmove_clock,
         }
     }
-}
\ No newline at end of file
+
+    pub fn as_move(&self) -> Move {
+        Move {
+            from: self.from,
+            to: self.to,
+            promotion: self.promotion,
+        }
+    }
+
+    pub fn to_uci(&self) -> String {
+        self.as_move().to_uci()
+    }
+
+    pub fn is_castling(&self) -> bool {
+        self.rook_move.is_some()
+    }
+
+    pub fn is_en_passant(&self) -> bool {
+        self.en_passant_capture.is_some()
+    }
+}
@@ -0,0 +1,169 @@
+use super::{Bitboard, CastleSide, Color, Game, Move, Piece, PieceType, Position, between, bishop_attacks, king_attacks, king_home, knight_attacks, line, pawn_attacks, rook_attacks, squares};
+
+const PROMOTION_PIECES: [PieceType; 4] = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight];
+
+impl Game {
+    /// Legal moves for the side to move, regardless of the game status, with
+    /// one entry per promotion piece. Checks and pins are resolved with masks
+    /// up front, so no move has to be tried out on a copy of the board.
+    pub(super) fn generate_legal_moves(&self) -> Vec<Move> {
+        let board = self.board();
+        let us = self.current_player();
+        let them = us.opposite();
+        let own = board.occupancy(us);
+        let enemy = board.occupancy(them);
+        let occupied = own | enemy;
+
+        let mut moves = Vec::with_capacity(64);
+        let king = squares(board.pieces(PieceType::King, us)).next();
+
+        // Squares a non-king move must land on: anywhere when not in check,
+        // otherwise capturing the checker or blocking its line.
+        let mut check_mask = !0;
+        let mut pinned = 0;
+
+        if let Some(king) = king {
+            let without_king = occupied & !(1 << king);
+            for to in squares(king_attacks(king) & !own) {
+                if board.attackers_to(to, them, without_king) == 0 {
+                    moves.push(Move::new(position(king), position(to)));
+                }
+            }
+
+            let checkers = board.attackers_to(king, them, occupied);
+            match checkers.count_ones() {
+                0 => self.push_castling_moves(king, &mut moves),
+                1 => check_mask = checkers | between(king, checkers.trailing_zeros() as usize),
+                _ => return moves,
+            }
+            pinned = self.pinned_pieces(king);
+        }
+
+        // A pinned piece may only move along the line through its king and pinner.
+        let allowed = |from: usize| match king {
+            Some(king) if pinned & (1 << from) != 0 => check_mask & line(king, from),
+            _ => check_mask,
+        };
+        let mut push_targets = |from: usize, targets: Bitboard| {
+            for to in squares(targets) {
+                moves.push(Move::new(position(from), position(to)));
+            }
+        };
+
+        for from in squares(board.pieces(PieceType::Knight, us) & !pinned) {
+            push_targets(from, knight_attacks(from) & !own & check_mask);
+        }
+        let queens = board.pieces(PieceType::Queen, us);
+        for from in squares(board.pieces(PieceType::Bishop, us) | queens) {
+            push_targets(from, bishop_attacks(from, occupied) & !own & allowed(from));
+        }
+        for from in squares(board.pieces(PieceType::Rook, us) | queens) {
+            push_targets(from, rook_attacks(from, occupied) & !own & allowed(from));
+        }
+
+        let (forward, start_row, promotion_row): (isize, usize, usize) = match us {
+            Color::White => (-8, 6, 0),
+            Color::Black => (8, 1, 7),
+        };
+        let en_passant = self.en_passant_target().map(Position::index);
+
+        for from in squares(board.pieces(PieceType::Pawn, us)) {
+            let mut targets = pawn_attacks(us, from) & enemy;
+            if let Some(one) = from.checked_add_signed(forward).filter(|&square| square < 64)
+                && occupied & (1 << one) == 0
+            {
+                targets |= 1 << one;
+                let two = one.wrapping_add_signed(forward);
+                if from / 8 == start_row && occupied & (1 << two) == 0 {
+                    targets |= 1 << two;
+                }
+            }
+
+            for to in squares(targets & allowed(from)) {
+                if to / 8 == promotion_row {
+                    for promotion in PROMOTION_PIECES {
+                        moves.push(Move::with_promotion(position(from), position(to), promotion));
+                    }
+                } else {
+                    moves.push(Move::new(position(from), position(to)));
+                }
+            }
+
+            if let Some(target) = en_passant
+                && pawn_attacks(us, from) & (1 << target) != 0
+                && self.is_en_passant_legal(from, target, king)
+            {
+                moves.push(Move::new(position(from), position(target)));
+            }
+        }
+
+        moves
+    }
+
+    /// Own pieces that are the only piece between the king and an enemy slider.
+    fn pinned_pieces(&self, king: usize) -> Bitboard {
+        let board = self.board();
+        let them = self.current_player().opposite();
+        let enemy = board.occupancy(them);
+        let queens = board.pieces(PieceType::Queen, them);
+
+        let snipers = (rook_attacks(king, enemy) & (board.pieces(PieceType::Rook, them) | queens))
+            | (bishop_attacks(king, enemy) & (board.pieces(PieceType::Bishop, them) | queens));
+
+        let mut pinned = 0;
+        for sniper in squares(snipers) {
+            let blockers = between(king, sniper) & board.occupied();
+            if blockers.count_ones() == 1 {
+                pinned |= blockers & board.occupancy(self.current_player());
+            }
+        }
+        pinned
+    }
+
+    /// En passant removes two pieces from a rank at once, which the pin masks
+    /// don't cover, so it is checked by looking at the resulting occupancy.
+    fn is_en_passant_legal(&self, from: usize, target: usize, king: Option<usize>) -> bool {
+        let Some(king) = king else {
+            return true;
+        };
+
+        let board = self.board();
+        let captured = (from / 8) * 8 + target % 8;
+        let occupied = (board.occupied() & !(1 << from) & !(1 << captured)) | 1 << target;
+        board.attackers_to(king, self.current_player().opposite(), occupied) & !(1 << captured) == 0
+    }
+
+    /// Castling moves when the king isn't in check: the right must still be
+    /// held, the squares up to the rook empty and the squares the king crosses
+    /// and lands on unattacked.
+    fn push_castling_moves(&self, king: usize, moves: &mut Vec<Move>) {
+        let board = self.board();
+        let us = self.current_player();
+        let home = king_home(us);
+        if king != home.index() {
+            return;
+        }
+
+        let occupied = board.occupied() & !(1 << king);
+        for side in [CastleSide::KingSide, CastleSide::QueenSide] {
+            let rook_from = side.rook_from(us);
+            let is_clear = self.castling_rights().has(us, side)
+                && board.get_piece(rook_from) == Some(Piece::new(PieceType::Rook, us))
+                && between(king, rook_from.index()) & occupied == 0;
+            if !is_clear {
+                continue;
+            }
+
+            let is_safe = [side.rook_to(us), side.king_to(us)]
+                .into_iter()
+                .all(|square| board.attackers_to(square.index(), us.opposite(), occupied) == 0);
+            if is_safe {
+                moves.push(Move::new(home, side.king_to(us)));
+            }
+        }
+    }
+}
+
+fn position(square: usize) -> Position {
+    Position { row: square / 8, col: square % 8 }
+}
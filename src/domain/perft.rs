@@ -0,0 +1,49 @@
+use super::{Game, Move};
+
+impl Game {
+    /// Counts the leaf nodes of the legal move tree `depth` plies deep from
+    /// the current position. Draws by rule don't cut the tree short, so the
+    /// counts can be compared with other move generators.
+    pub fn perft(&self, depth: u32) -> u64 {
+        let mut game = self.clone();
+        perft_recursive(&mut game, depth)
+    }
+
+    /// Perft count below each legal move, for tracking down where a move
+    /// generator disagrees with a reference engine.
+    pub fn divide(&self, depth: u32) -> Vec<(Move, u64)> {
+        if depth == 0 {
+            return Vec::new();
+        }
+
+        let mut game = self.clone();
+        game.generate_legal_moves()
+            .into_iter()
+            .map(|mv| {
+                game.push_move(mv);
+                let nodes = perft_recursive(&mut game, depth - 1);
+                game.pop_move();
+                (mv, nodes)
+            })
+            .collect()
+    }
+}
+
+fn perft_recursive(game: &mut Game, depth: u32) -> u64 {
+    if depth == 0 {
+        return 1;
+    }
+
+    let moves = game.generate_legal_moves();
+    if depth == 1 {
+        return moves.len() as u64;
+    }
+
+    let mut nodes = 0;
+    for mv in moves {
+        game.push_move(mv);
+        nodes += perft_recursive(game, depth - 1);
+        game.pop_move();
+    }
+    nodes
+}
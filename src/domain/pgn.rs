@@ -0,0 +1,496 @@
+use std::fmt;
+
+use super::{Clock, Color, FenError, Game, GameStatus, SanError, STARTING_FEN, format_clock_time};
+
+const MAX_LINE_LENGTH: usize = 80;
+
+/// Values for the Seven Tag Roster. `Result` is taken from the game itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        PgnTags {
+            event: "?".to_string(),
+            site: "?".to_string(),
+            date: "????.??.??".to_string(),
+            round: "?".to_string(),
+            white: "?".to_string(),
+            black: "?".to_string(),
+        }
+    }
+}
+
+/// A game read from a PGN file. `game` holds the full move history with the
+/// final position on the board.
+#[derive(Debug, Clone)]
+pub struct PgnGame {
+    /// Tag pairs in the order they appeared.
+    pub tags: Vec<(String, String)>,
+    pub game: Game,
+    /// Termination marker from the movetext, e.g. `1-0` or `*`.
+    pub result: String,
+}
+
+impl PgnGame {
+    pub fn tag(&self, name: &str) -> Option<&str> {
+        self.tags
+            .iter()
+            .find(|(tag_name, _)| tag_name == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PgnErrorKind {
+    MalformedTag,
+    UnterminatedComment,
+    UnbalancedVariation,
+    UnexpectedToken,
+    InvalidFen(FenError),
+    InvalidMove(SanError),
+    NoGames,
+}
+
+/// Where and why reading a PGN failed. `game_index` counts from 0 and `ply`
+/// is the number of halfmoves read in that game up to and including the
+/// offending token, so 0 means the error is in the tag section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PgnError {
+    pub game_index: usize,
+    pub ply: usize,
+    pub token: String,
+    pub kind: PgnErrorKind,
+}
+
+impl fmt::Display for PgnErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PgnErrorKind::MalformedTag => write!(f, "malformed tag pair"),
+            PgnErrorKind::UnterminatedComment => write!(f, "comment is never closed"),
+            PgnErrorKind::UnbalancedVariation => write!(f, "unbalanced variation parentheses"),
+            PgnErrorKind::UnexpectedToken => write!(f, "unexpected token"),
+            PgnErrorKind::InvalidFen(error) => write!(f, "invalid FEN tag: {}", error),
+            PgnErrorKind::InvalidMove(error) => write!(f, "{}", error),
+            PgnErrorKind::NoGames => write!(f, "no games found"),
+        }
+    }
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "game {}, ply {}, token '{}': {}",
+            self.game_index + 1,
+            self.ply,
+            self.token,
+            self.kind
+        )
+    }
+}
+
+impl std::error::Error for PgnError {}
+
+impl GameStatus {
+    /// PGN game termination marker: `1-0`, `0-1`, `1/2-1/2` or `*` while the game is undecided.
+    pub fn pgn_result(&self) -> &'static str {
+        match self {
+            GameStatus::Checkmate(Color::White) | GameStatus::TimeForfeit(Color::White) => "1-0",
+            GameStatus::Checkmate(Color::Black) | GameStatus::TimeForfeit(Color::Black) => "0-1",
+            GameStatus::Draw(_) => "1/2-1/2",
+            GameStatus::Ongoing | GameStatus::Check => "*",
+        }
+    }
+}
+
+impl Game {
+    /// Replays the first game of a PGN file.
+    pub fn from_pgn(pgn: &str) -> Result<Game, PgnError> {
+        parse_pgn(pgn)?
+            .into_iter()
+            .next()
+            .map(|pgn_game| pgn_game.game)
+            .ok_or(PgnError {
+                game_index: 0,
+                ply: 0,
+                token: String::new(),
+                kind: PgnErrorKind::NoGames,
+            })
+    }
+
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_tags(&PgnTags::default())
+    }
+
+    /// Exports the moves leading to the current position. Games that didn't
+    /// start from the standard position get `SetUp` and `FEN` tags.
+    pub fn to_pgn_with_tags(&self, tags: &PgnTags) -> String {
+        self.write_pgn(tags, None)
+    }
+
+    /// Like `to_pgn_with_tags`, adding a `TimeControl` tag and a `%clk`
+    /// comment with the mover's remaining time after each move. Moves played
+    /// before the clock was set up get no comment.
+    pub fn to_pgn_with_clock(&self, tags: &PgnTags, clock: &Clock) -> String {
+        self.write_pgn(tags, Some(clock))
+    }
+
+    fn write_pgn(&self, tags: &PgnTags, clock: Option<&Clock>) -> String {
+        let mut start = self.clone();
+        while start.undo_move() {}
+
+        let result = self.status().pgn_result();
+        let mut pgn = String::new();
+
+        for (name, value) in [
+            ("Event", tags.event.as_str()),
+            ("Site", tags.site.as_str()),
+            ("Date", tags.date.as_str()),
+            ("Round", tags.round.as_str()),
+            ("White", tags.white.as_str()),
+            ("Black", tags.black.as_str()),
+            ("Result", result),
+        ] {
+            pgn.push_str(&format_tag(name, value));
+        }
+
+        if let Some(clock) = clock {
+            pgn.push_str(&format_tag("TimeControl", &clock.control().to_string()));
+        }
+
+        let start_fen = start.to_fen();
+        if start_fen != STARTING_FEN {
+            pgn.push_str(&format_tag("SetUp", "1"));
+            pgn.push_str(&format_tag("FEN", &start_fen));
+        }
+        pgn.push('\n');
+
+        let mut tokens = Vec::new();
+        let mut fullmove_number = start.fullmove_number();
+        let mut color = start.current_player();
+        let san_history = self.san_history();
+        let mut after_comment = false;
+        for (i, san) in san_history.into_iter().enumerate() {
+            match color {
+                Color::White => tokens.push(format!("{}.", fullmove_number)),
+                // A comment after White's move is followed by the move number again.
+                Color::Black if i == 0 || after_comment => tokens.push(format!("{}...", fullmove_number)),
+                Color::Black => {}
+            }
+            tokens.push(san);
+
+            let move_time = clock.and_then(|clock| clock.time_after_ply(i + 1));
+            after_comment = move_time.is_some();
+            if let Some(time) = move_time {
+                tokens.push(format!("{{[%clk {}]}}", format_clock_time(time)));
+            }
+
+            if color == Color::Black {
+                fullmove_number += 1;
+            }
+            color = color.opposite();
+        }
+        tokens.push(result.to_string());
+
+        pgn.push_str(&wrap_tokens(&tokens));
+        pgn.push('\n');
+        pgn
+    }
+}
+
+enum Token {
+    Tag(String, String),
+    Move(String),
+    Result(String),
+}
+
+/// Reads every game in `pgn`, replaying the main line of each through
+/// `Game::make_move`. Comments, NAGs, move numbers and variations are skipped.
+pub fn parse_pgn(pgn: &str) -> Result<Vec<PgnGame>, PgnError> {
+    let mut games = Vec::new();
+    let mut builder = GameBuilder::new(0);
+
+    for token in tokenize(pgn)? {
+        match token {
+            Token::Tag(name, value) => {
+                if builder.has_moves {
+                    games.push(builder.finish("*".to_string())?);
+                    builder = GameBuilder::new(games.len());
+                }
+                builder.tags.push((name, value));
+            }
+            Token::Move(san) => builder.play(&san)?,
+            Token::Result(result) => {
+                games.push(builder.finish(result)?);
+                builder = GameBuilder::new(games.len());
+            }
+        }
+    }
+
+    if builder.has_moves || !builder.tags.is_empty() {
+        games.push(builder.finish("*".to_string())?);
+    }
+
+    if games.is_empty() {
+        return Err(PgnError {
+            game_index: 0,
+            ply: 0,
+            token: String::new(),
+            kind: PgnErrorKind::NoGames,
+        });
+    }
+
+    Ok(games)
+}
+
+struct GameBuilder {
+    index: usize,
+    tags: Vec<(String, String)>,
+    game: Option<Game>,
+    has_moves: bool,
+    ply: usize,
+}
+
+impl GameBuilder {
+    fn new(index: usize) -> Self {
+        GameBuilder {
+            index,
+            tags: Vec::new(),
+            game: None,
+            has_moves: false,
+            ply: 0,
+        }
+    }
+
+    fn error(&self, token: &str, kind: PgnErrorKind) -> PgnError {
+        PgnError {
+            game_index: self.index,
+            ply: self.ply,
+            token: token.to_string(),
+            kind,
+        }
+    }
+
+    /// The game is created lazily so that a `FEN` tag anywhere in the tag
+    /// section is honoured.
+    fn game(&mut self) -> Result<&mut Game, PgnError> {
+        if self.game.is_none() {
+            let fen = self
+                .tags
+                .iter()
+                .find(|(name, _)| name == "FEN")
+                .map(|(_, value)| value.clone());
+
+            let game = match fen {
+                Some(fen) => Game::from_fen(&fen).map_err(|error| self.error(&fen, PgnErrorKind::InvalidFen(error)))?,
+                None => Game::new(),
+            };
+            self.game = Some(game);
+        }
+        Ok(self.game.as_mut().unwrap())
+    }
+
+    fn play(&mut self, san: &str) -> Result<(), PgnError> {
+        self.has_moves = true;
+        self.ply += 1;
+
+        let game = self.game()?;
+        let result = game.parse_san(san).and_then(|mv| {
+            game.make_move(mv).map_err(|_| SanError::Illegal(san.to_string()))
+        });
+        result.map_err(|error| self.error(san, PgnErrorKind::InvalidMove(error)))
+    }
+
+    fn finish(mut self, result: String) -> Result<PgnGame, PgnError> {
+        let game = self.game()?.clone();
+        Ok(PgnGame { tags: self.tags, game, result })
+    }
+}
+
+fn tokenize(pgn: &str) -> Result<Vec<Token>, PgnError> {
+    let mut tokens = Vec::new();
+    let chars: Vec<char> = pgn.chars().collect();
+    let mut i = 0;
+    let mut variation_depth = 0;
+    let mut at_line_start = true;
+
+    let error = |tokens: &[Token], token: &str, kind: PgnErrorKind| {
+        let game_index = tokens.iter().filter(|t| matches!(t, Token::Result(_))).count();
+        let ply = tokens
+            .iter()
+            .rev()
+            .take_while(|t| !matches!(t, Token::Result(_)))
+            .filter(|t| matches!(t, Token::Move(_)))
+            .count();
+        PgnError {
+            game_index,
+            ply,
+            token: token.to_string(),
+            kind,
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        // A `%` in the first column escapes the whole line.
+        if at_line_start && c == '%' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        at_line_start = c == '\n';
+
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '{' => match chars[i..].iter().position(|&ch| ch == '}') {
+                Some(end) => i += end + 1,
+                None => return Err(error(&tokens, "{", PgnErrorKind::UnterminatedComment)),
+            },
+            '}' => return Err(error(&tokens, "}", PgnErrorKind::UnexpectedToken)),
+            ';' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            }
+            '(' => {
+                variation_depth += 1;
+                i += 1;
+            }
+            ')' => {
+                if variation_depth == 0 {
+                    return Err(error(&tokens, ")", PgnErrorKind::UnbalancedVariation));
+                }
+                variation_depth -= 1;
+                i += 1;
+            }
+            '[' => {
+                let end = tag_end(&chars, i).ok_or_else(|| error(&tokens, "[", PgnErrorKind::MalformedTag))?;
+                // Tags can't appear inside a variation, but some programs put
+                // bracketed annotations there; they are skipped like the
+                // rest of the variation.
+                if variation_depth == 0 {
+                    let text: String = chars[i + 1..end].iter().collect();
+                    let tag = parse_tag(&text).ok_or_else(|| error(&tokens, &text, PgnErrorKind::MalformedTag))?;
+                    tokens.push(tag);
+                }
+                i = end + 1;
+            }
+            _ => {
+                let start = i;
+                while i < chars.len() && !chars[i].is_whitespace() && !"{};()[".contains(chars[i]) {
+                    i += 1;
+                }
+                let word: String = chars[start..i].iter().collect();
+
+                if variation_depth > 0 {
+                    continue;
+                }
+                if let Some(token) = classify_word(&word) {
+                    tokens.push(token);
+                }
+            }
+        }
+    }
+
+    if variation_depth > 0 {
+        return Err(error(&tokens, "(", PgnErrorKind::UnbalancedVariation));
+    }
+
+    Ok(tokens)
+}
+
+/// Index of the `]` closing the tag that opens at `start`, skipping over
+/// quoted strings so that a value may contain `]` or an escaped `\"`.
+fn tag_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut in_string = false;
+    let mut i = start + 1;
+    while i < chars.len() {
+        match chars[i] {
+            '\\' if in_string => i += 1,
+            '"' => in_string = !in_string,
+            ']' if !in_string => return Some(i),
+            _ => {}
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parses the inside of `[Name "value"]`, unescaping `\"` and `\\`.
+fn parse_tag(text: &str) -> Option<Token> {
+    let text = text.trim();
+    let (name, rest) = text.split_once(char::is_whitespace)?;
+    let quoted = rest.trim();
+    if quoted.len() < 2 || !quoted.starts_with('"') || !quoted.ends_with('"') {
+        return None;
+    }
+
+    let mut value = String::new();
+    let mut chars = quoted[1..quoted.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            value.push(chars.next()?);
+        } else {
+            value.push(c);
+        }
+    }
+
+    Some(Token::Tag(name.to_string(), value))
+}
+
+/// Turns a movetext word into a token, dropping move numbers, NAGs and a
+/// separate `e.p.` after en passant captures.
+/// Words like `12.e4` keep the move after the number.
+fn classify_word(word: &str) -> Option<Token> {
+    if matches!(word, "1-0" | "0-1" | "1/2-1/2" | "*") {
+        return Some(Token::Result(word.to_string()));
+    }
+    if word.starts_with('$') || word == "e.p." {
+        return None;
+    }
+
+    let digits = word.chars().take_while(char::is_ascii_digit).count();
+    let san = if digits > 0 && (digits == word.len() || word[digits..].starts_with('.')) {
+        word[digits..].trim_start_matches('.')
+    } else {
+        word
+    };
+    if san.is_empty() {
+        return None;
+    }
+
+    Some(Token::Move(san.replace("0-0-0", "O-O-O").replace("0-0", "O-O")))
+}
+
+fn format_tag(name: &str, value: &str) -> String {
+    let escaped = value.replace('\\', "\\\\").replace('"', "\\\"");
+    format!("[{} \"{}\"]\n", name, escaped)
+}
+
+fn wrap_tokens(tokens: &[String]) -> String {
+    let mut text = String::new();
+    let mut line_length = 0;
+
+    for token in tokens {
+        if line_length > 0 && line_length + 1 + token.len() > MAX_LINE_LENGTH {
+            text.push('\n');
+            line_length = 0;
+        } else if line_length > 0 {
+            text.push(' ');
+            line_length += 1;
+        }
+        text.push_str(token);
+        line_length += token.len();
+    }
+    text
+}
@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum PieceType {
     King,
     Queen,
@@ -8,13 +8,22 @@ pub enum PieceType {
     Pawn,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Color {
     White,
     Black,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+impl Color {
+    pub fn opposite(self) -> Color {
+        match self {
+            Color::White => Color::Black,
+            Color::Black => Color::White,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: Color,
@@ -25,6 +34,36 @@ impl Piece {
         Piece { piece_type, color }
     }
 
+    /// FEN letter for the piece: uppercase for White, lowercase for Black.
+    pub fn fen_char(&self) -> char {
+        let c = match self.piece_type {
+            PieceType::King => 'k',
+            PieceType::Queen => 'q',
+            PieceType::Rook => 'r',
+            PieceType::Bishop => 'b',
+            PieceType::Knight => 'n',
+            PieceType::Pawn => 'p',
+        };
+        match self.color {
+            Color::White => c.to_ascii_uppercase(),
+            Color::Black => c,
+        }
+    }
+
+    pub fn from_fen_char(c: char) -> Option<Self> {
+        let piece_type = match c.to_ascii_lowercase() {
+            'k' => PieceType::King,
+            'q' => PieceType::Queen,
+            'r' => PieceType::Rook,
+            'b' => PieceType::Bishop,
+            'n' => PieceType::Knight,
+            'p' => PieceType::Pawn,
+            _ => return None,
+        };
+        let color = if c.is_ascii_uppercase() { Color::White } else { Color::Black };
+        Some(Piece::new(piece_type, color))
+    }
+
     pub fn unicode_symbol(&self) -> char {
         match (self.color, self.piece_type) {
             (Color::White, PieceType::King) => '♔',
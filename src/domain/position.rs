@@ -1,4 +1,4 @@
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Position {
     pub row: usize,
     pub col: usize,
@@ -34,7 +34,7 @@ impl Position {
         Some(Position { row, col })
     }
 
-    pub fn to_chess_notation(&self) -> String {
+    pub fn to_chess_notation(self) -> String {
         let col_char = match self.col {
             0 => 'a', 1 => 'b', 2 => 'c', 3 => 'd',
             4 => 'e', 5 => 'f', 6 => 'g', 7 => 'h',
@@ -50,7 +50,16 @@ impl Position {
         format!("{}{}", col_char, row_char)
     }
 
-    pub fn is_valid(&self) -> bool {
+    pub fn is_valid(self) -> bool {
         self.row < 8 && self.col < 8
     }
+
+    /// Index of the square in a `Bitboard`: a8 is 0, h8 is 7 and h1 is 63.
+    pub fn index(self) -> usize {
+        self.row * 8 + self.col
+    }
+
+    pub fn from_index(index: usize) -> Option<Self> {
+        Position::new(index / 8, index % 8)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,259 @@
+use std::fmt;
+
+use super::{Game, GameStatus, Move, PieceType, Position};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SanError {
+    /// The text isn't shaped like a SAN move.
+    Malformed(String),
+    /// Well-formed, but no legal move matches it.
+    Illegal(String),
+    /// More than one legal move matches it.
+    Ambiguous(String),
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SanError::Malformed(san) => write!(f, "'{}' is not a valid SAN move", san),
+            SanError::Illegal(san) => write!(f, "'{}' is not a legal move", san),
+            SanError::Ambiguous(san) => write!(f, "'{}' matches more than one legal move", san),
+        }
+    }
+}
+
+impl std::error::Error for SanError {}
+
+impl PieceType {
+    /// Uppercase SAN letter; pawns have none.
+    pub fn san_letter(self) -> Option<char> {
+        match self {
+            PieceType::King => Some('K'),
+            PieceType::Queen => Some('Q'),
+            PieceType::Rook => Some('R'),
+            PieceType::Bishop => Some('B'),
+            PieceType::Knight => Some('N'),
+            PieceType::Pawn => None,
+        }
+    }
+}
+
+impl Game {
+    /// Standard Algebraic Notation for `mv`, which must be legal in the current
+    /// position, e.g. `Nbd7`, `exd6`, `O-O-O` or `e8=Q+`.
+    pub fn move_to_san(&self, mv: Move) -> String {
+        let Some(piece) = self.board().get_piece(mv.from) else {
+            return String::new();
+        };
+
+        let mut san = String::new();
+        let is_castling = piece.piece_type == PieceType::King && mv.from.col.abs_diff(mv.to.col) == 2;
+
+        if is_castling {
+            san.push_str(if mv.to.col > mv.from.col { "O-O" } else { "O-O-O" });
+        } else {
+            let is_capture = self.board().get_piece(mv.to).is_some()
+                || (piece.piece_type == PieceType::Pawn && mv.from.col != mv.to.col);
+
+            match piece.piece_type.san_letter() {
+                Some(letter) => {
+                    san.push(letter);
+                    san.push_str(&self.disambiguation(mv));
+                }
+                None if is_capture => san.push(file_char(mv.from.col)),
+                None => {}
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&mv.to.to_chess_notation());
+
+            if let Some(promotion) = mv.promotion.and_then(PieceType::san_letter) {
+                san.push('=');
+                san.push(promotion);
+            }
+        }
+
+        let mut after = self.clone();
+        if after.make_move(mv).is_ok() {
+            match after.status() {
+                GameStatus::Checkmate(_) => san.push('#'),
+                _ if after.is_in_check(after.current_player()) => san.push('+'),
+                _ => {}
+            }
+        }
+
+        san
+    }
+
+    /// Resolves a SAN move such as `Nbd7`, `exd6`, `O-O` or `e8=Q+` to the
+    /// matching legal move in the current position.
+    ///
+    /// Input is read leniently, the way people type moves: check and
+    /// annotation suffixes (`+`, `#`, `!`, `?`) and a trailing `e.p.` are
+    /// ignored, castling may be written with zeros, the `=` before a
+    /// promotion piece is optional and the promotion piece may be lowercase.
+    pub fn parse_san(&self, san: &str) -> Result<Move, SanError> {
+        let malformed = || SanError::Malformed(san.to_string());
+        let body = san
+            .trim()
+            .trim_end_matches(['+', '#', '!', '?'])
+            .trim_end_matches("e.p.")
+            .trim_end()
+            .trim_end_matches(['+', '#']);
+
+        let castling_side = match body {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(king_to_col) = castling_side {
+            return self
+                .legal_moves()
+                .into_iter()
+                .find(|mv| {
+                    self.board().get_piece(mv.from).map(|p| p.piece_type) == Some(PieceType::King)
+                        && mv.from.col == 4
+                        && mv.to.col == king_to_col
+                        && mv.from.row == mv.to.row
+                })
+                .ok_or_else(|| SanError::Illegal(san.to_string()));
+        }
+
+        let mut chars: Vec<char> = body.chars().collect();
+
+        // A promotion piece follows either `=` or the destination rank, which
+        // keeps a lowercase `b` from being mistaken for the b-file.
+        let mut promotion = None;
+        if chars.len() >= 2
+            && let Some(piece_type) = piece_type_from_letter(chars[chars.len() - 1].to_ascii_uppercase())
+            && matches!(chars[chars.len() - 2], '=' | '1'..='8')
+        {
+            promotion = Some(piece_type);
+            chars.pop();
+            if chars.last() == Some(&'=') {
+                chars.pop();
+            }
+        }
+
+        if chars.len() < 2 {
+            return Err(malformed());
+        }
+        let destination: String = chars.split_off(chars.len() - 2).into_iter().collect();
+        let to = Position::from_chess_notation(&destination).ok_or_else(malformed)?;
+
+        if chars.first() == Some(&'P') {
+            chars.remove(0);
+        }
+
+        let mut piece_type = PieceType::Pawn;
+        if let Some(&first) = chars.first()
+            && let Some(letter_type) = piece_type_from_letter(first)
+        {
+            piece_type = letter_type;
+            chars.remove(0);
+        }
+
+        if chars.last() == Some(&'x') {
+            chars.pop();
+        }
+
+        let mut from_col = None;
+        let mut from_row = None;
+        for c in chars {
+            match c {
+                'a'..='h' if from_col.is_none() && from_row.is_none() => from_col = Some(c as usize - 'a' as usize),
+                '1'..='8' if from_row.is_none() => from_row = Some('8' as usize - c as usize),
+                _ => return Err(malformed()),
+            }
+        }
+
+        let candidates: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|mv| {
+                mv.to == to
+                    && mv.promotion == promotion
+                    && from_col.is_none_or(|col| mv.from.col == col)
+                    && from_row.is_none_or(|row| mv.from.row == row)
+                    && self.board().get_piece(mv.from).map(|p| p.piece_type) == Some(piece_type)
+            })
+            .collect();
+
+        match candidates.as_slice() {
+            [mv] => Ok(*mv),
+            [] => Err(SanError::Illegal(san.to_string())),
+            _ => Err(SanError::Ambiguous(san.to_string())),
+        }
+    }
+
+    /// SAN of every move in `move_history`, oldest first.
+    pub fn san_history(&self) -> Vec<String> {
+        let mut sans = self.recorded_san();
+        sans.truncate(self.current_ply());
+        sans
+    }
+
+    /// SAN of every move in `recorded_moves`, oldest first.
+    pub fn recorded_san(&self) -> Vec<String> {
+        let mut replay = self.clone();
+        replay.go_to_ply(0);
+
+        let mut sans = Vec::with_capacity(self.recorded_moves().len());
+        for record in self.recorded_moves() {
+            sans.push(replay.move_to_san(record.as_move()));
+            replay.redo_move();
+        }
+        sans
+    }
+
+    /// Prefix needed to tell `mv` apart from other moves of the same piece type
+    /// to the same square: the source file if that is enough, otherwise the
+    /// rank, otherwise the full square.
+    fn disambiguation(&self, mv: Move) -> String {
+        let Some(piece) = self.board().get_piece(mv.from) else {
+            return String::new();
+        };
+
+        let rivals: Vec<Move> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|other| {
+                other.to == mv.to
+                    && other.from != mv.from
+                    && other.promotion == mv.promotion
+                    && self.board().get_piece(other.from) == Some(piece)
+            })
+            .collect();
+
+        if rivals.is_empty() {
+            String::new()
+        } else if rivals.iter().all(|other| other.from.col != mv.from.col) {
+            file_char(mv.from.col).to_string()
+        } else if rivals.iter().all(|other| other.from.row != mv.from.row) {
+            rank_char(mv.from.row).to_string()
+        } else {
+            mv.from.to_chess_notation()
+        }
+    }
+}
+
+fn piece_type_from_letter(c: char) -> Option<PieceType> {
+    match c {
+        'K' => Some(PieceType::King),
+        'Q' => Some(PieceType::Queen),
+        'R' => Some(PieceType::Rook),
+        'B' => Some(PieceType::Bishop),
+        'N' => Some(PieceType::Knight),
+        _ => None,
+    }
+}
+
+fn file_char(col: usize) -> char {
+    (b'a' + col as u8) as char
+}
+
+fn rank_char(row: usize) -> char {
+    (b'8' - row as u8) as char
+}
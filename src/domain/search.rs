@@ -0,0 +1,459 @@
+use std::cmp::Reverse;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{Game, Move, PIECE_VALUES, PieceType};
+
+/// Score of delivering mate right now; mate in `n` plies scores `MATE_SCORE - n`.
+pub const MATE_SCORE: i32 = 30_000;
+
+const INFINITY: i32 = 32_000;
+const MAX_PLY: usize = 128;
+const TABLE_SIZE: usize = 1 << 17;
+
+/// When to stop searching. Limits left unset don't apply, and with none set
+/// the search only ends when its stop flag is raised.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SearchLimits {
+    pub depth: Option<u32>,
+    pub move_time: Option<Duration>,
+    pub nodes: Option<u64>,
+}
+
+impl SearchLimits {
+    pub fn depth(depth: u32) -> Self {
+        SearchLimits { depth: Some(depth), ..SearchLimits::default() }
+    }
+
+    pub fn move_time(move_time: Duration) -> Self {
+        SearchLimits { move_time: Some(move_time), ..SearchLimits::default() }
+    }
+}
+
+/// What the search found at the deepest depth it completed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    /// `None` only when the side to move has no legal moves.
+    pub best_move: Option<Move>,
+    /// Centipawns from the point of view of the side to move; see `mate_in`.
+    pub score: i32,
+    pub depth: u32,
+    pub nodes: u64,
+    pub elapsed: Duration,
+    pub principal_variation: Vec<Move>,
+}
+
+impl SearchResult {
+    /// Full moves until mate if the score is a mate score: positive when the
+    /// side to move mates, negative when it gets mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        let plies = MATE_SCORE - self.score.abs();
+        if plies > MAX_PLY as i32 {
+            return None;
+        }
+        let moves = (plies + 1) / 2;
+        Some(if self.score > 0 { moves } else { -moves })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Bound {
+    Exact,
+    Lower,
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TableEntry {
+    key: u64,
+    depth: u32,
+    score: i32,
+    bound: Bound,
+    best_move: Option<Move>,
+}
+
+/// Iterative-deepening alpha-beta search with a transposition table, MVV-LVA,
+/// killer and history move ordering, and a quiescence search over captures.
+pub struct Searcher {
+    limits: SearchLimits,
+    stop: Arc<AtomicBool>,
+    stopped: bool,
+    start: Instant,
+    nodes: u64,
+    table: Vec<Option<TableEntry>>,
+    killers: Vec<[Option<Move>; 2]>,
+    history: Vec<[i32; 64]>,
+    /// Keys of the positions from the start of the game to the current node,
+    /// for spotting repetitions.
+    keys: Vec<u64>,
+    root_best_move: Option<Move>,
+}
+
+impl Searcher {
+    pub fn new(limits: SearchLimits) -> Self {
+        Searcher {
+            limits,
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+            start: Instant::now(),
+            nodes: 0,
+            table: vec![None; TABLE_SIZE],
+            killers: vec![[None; 2]; MAX_PLY],
+            history: vec![[0; 64]; 64],
+            keys: Vec::new(),
+            root_best_move: None,
+        }
+    }
+
+    /// Setting this flag from another thread ends the search early. The result
+    /// of the last completed depth is still returned.
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        Arc::clone(&self.stop)
+    }
+
+    pub fn search(&mut self, game: &Game) -> SearchResult {
+        self.search_with(game, |_| {})
+    }
+
+    /// Like `search`, calling `on_iteration` after every completed depth.
+    pub fn search_with(&mut self, game: &Game, mut on_iteration: impl FnMut(&SearchResult)) -> SearchResult {
+        self.start = Instant::now();
+        self.stopped = false;
+        self.nodes = 0;
+        self.keys = game.position_keys().to_vec();
+
+        let mut game = game.clone();
+        let mut result = SearchResult {
+            best_move: game.generate_legal_moves().first().copied(),
+            score: 0,
+            depth: 0,
+            nodes: 0,
+            elapsed: Duration::ZERO,
+            principal_variation: Vec::new(),
+        };
+        if result.best_move.is_none() {
+            return result;
+        }
+
+        let max_depth = self.limits.depth.unwrap_or(MAX_PLY as u32 / 2).clamp(1, MAX_PLY as u32 / 2);
+        for depth in 1..=max_depth {
+            self.root_best_move = None;
+            let score = self.alpha_beta(&mut game, depth, -INFINITY, INFINITY, 0);
+            if self.stopped {
+                break;
+            }
+
+            let principal_variation = self.principal_variation(&game, depth);
+            result = SearchResult {
+                best_move: self.root_best_move.or(result.best_move),
+                score,
+                depth,
+                nodes: self.nodes,
+                elapsed: self.start.elapsed(),
+                principal_variation,
+            };
+            on_iteration(&result);
+
+            // Searching deeper won't find a faster mate than one already seen.
+            if let Some(moves) = result.mate_in()
+                && moves.unsigned_abs() * 2 <= depth
+            {
+                break;
+            }
+            // The next depth takes longer than all the previous ones together.
+            if let Some(move_time) = self.limits.move_time
+                && self.start.elapsed() * 2 > move_time
+            {
+                break;
+            }
+        }
+
+        result.nodes = self.nodes;
+        result.elapsed = self.start.elapsed();
+        result
+    }
+
+    fn alpha_beta(&mut self, game: &mut Game, depth: u32, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+
+        if ply > 0 && self.is_draw(game) {
+            return 0;
+        }
+
+        let in_check = game.is_in_check(game.current_player());
+        // Look one ply further when in check so forced sequences aren't cut short.
+        let depth = if in_check { depth + 1 } else { depth };
+        if depth == 0 || ply >= MAX_PLY - 1 {
+            return self.quiescence(game, alpha, beta, ply);
+        }
+        self.nodes += 1;
+
+        let key = game.zobrist_key();
+        let entry = self.probe(key);
+        if ply > 0
+            && let Some(entry) = entry
+            && entry.depth >= depth
+        {
+            let score = score_from_table(entry.score, ply);
+            let usable = match entry.bound {
+                Bound::Exact => true,
+                Bound::Lower => score >= beta,
+                Bound::Upper => score <= alpha,
+            };
+            if usable {
+                return score;
+            }
+        }
+
+        let mut moves = game.generate_legal_moves();
+        if moves.is_empty() {
+            return if in_check { -MATE_SCORE + ply as i32 } else { 0 };
+        }
+        self.order_moves(game, &mut moves, entry.and_then(|entry| entry.best_move), ply);
+
+        let original_alpha = alpha;
+        let mut best_score = -INFINITY;
+        let mut best_move = None;
+
+        for mv in moves {
+            let quiet = !is_capture(game, mv) && mv.promotion.is_none();
+            let score = -self.search_child(game, mv, |searcher, game| {
+                searcher.alpha_beta(game, depth - 1, -beta, -alpha, ply + 1)
+            });
+            if self.stopped {
+                return 0;
+            }
+
+            if score > best_score {
+                best_score = score;
+                best_move = Some(mv);
+                if ply == 0 {
+                    self.root_best_move = Some(mv);
+                }
+            }
+            alpha = alpha.max(score);
+
+            if alpha >= beta {
+                if quiet {
+                    self.remember_quiet_cutoff(mv, depth, ply);
+                }
+                break;
+            }
+        }
+
+        let bound = if best_score >= beta {
+            Bound::Lower
+        } else if best_score > original_alpha {
+            Bound::Exact
+        } else {
+            Bound::Upper
+        };
+        self.store(TableEntry { key, depth, score: score_to_table(best_score, ply), bound, best_move });
+
+        best_score
+    }
+
+    /// Only follows captures and queen promotions until the position is quiet,
+    /// so the evaluation isn't taken in the middle of an exchange.
+    fn quiescence(&mut self, game: &mut Game, mut alpha: i32, beta: i32, ply: usize) -> i32 {
+        if self.should_stop() {
+            return 0;
+        }
+        self.nodes += 1;
+
+        // The side to move can usually do at least as well as standing still.
+        let stand_pat = game.evaluate();
+        if stand_pat >= beta || ply >= MAX_PLY - 1 {
+            return stand_pat;
+        }
+        alpha = alpha.max(stand_pat);
+
+        let mut moves: Vec<Move> = game
+            .generate_legal_moves()
+            .into_iter()
+            .filter(|&mv| is_capture(game, mv) || mv.promotion == Some(PieceType::Queen))
+            .collect();
+        self.order_moves(game, &mut moves, None, ply);
+
+        for mv in moves {
+            let score = -self.search_child(game, mv, |searcher, game| searcher.quiescence(game, -beta, -alpha, ply + 1));
+            if self.stopped {
+                return 0;
+            }
+            if score >= beta {
+                return score;
+            }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+
+    fn search_child(&mut self, game: &mut Game, mv: Move, search: impl FnOnce(&mut Self, &mut Game) -> i32) -> i32 {
+        game.push_move(mv);
+        self.keys.push(game.position_key());
+        let score = search(self, game);
+        self.keys.pop();
+        game.pop_move();
+        score
+    }
+
+    fn should_stop(&mut self) -> bool {
+        if self.stopped {
+            return true;
+        }
+        if self.nodes.is_multiple_of(1024) {
+            self.stopped = self.stop.load(Ordering::Relaxed)
+                || self.limits.move_time.is_some_and(|move_time| self.start.elapsed() >= move_time);
+        }
+        if self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes) {
+            self.stopped = true;
+        }
+        self.stopped
+    }
+
+    /// Draws by the fifty-move rule, insufficient material or a repetition.
+    /// A single repetition is enough, since whoever repeated could repeat again.
+    /// Mate on the hundredth halfmove still wins, so the fifty-move rule needs
+    /// a legal move.
+    fn is_draw(&self, game: &Game) -> bool {
+        if game.board().has_insufficient_material() {
+            return true;
+        }
+        if game.halfmove_clock() >= 100 {
+            return game.has_legal_move();
+        }
+
+        // Only positions since the last capture or pawn move with the same
+        // side to move can repeat the current one.
+        let Some((&key, earlier)) = self.keys.split_last() else {
+            return false;
+        };
+        earlier
+            .iter()
+            .rev()
+            .take(game.halfmove_clock() as usize)
+            .skip(1)
+            .step_by(2)
+            .any(|&earlier_key| earlier_key == key)
+    }
+
+    /// Previous best move first, then captures of the most valuable piece by
+    /// the least valuable attacker, then killers and moves with a good history.
+    fn order_moves(&self, game: &Game, moves: &mut [Move], best_move: Option<Move>, ply: usize) {
+        moves.sort_by_cached_key(|&mv| {
+            let score = if Some(mv) == best_move {
+                i32::MAX
+            } else if let Some(victim) = captured_piece(game, mv) {
+                let attacker = game.board().get_piece(mv.from).map_or(0, |piece| PIECE_VALUES[piece.piece_type as usize]);
+                1_000_000 + PIECE_VALUES[victim as usize] * 10 - attacker
+            } else if mv.promotion == Some(PieceType::Queen) {
+                900_000
+            } else if self.killers[ply][0] == Some(mv) {
+                800_000
+            } else if self.killers[ply][1] == Some(mv) {
+                700_000
+            } else {
+                self.history[mv.from.index()][mv.to.index()]
+            };
+            Reverse(score)
+        });
+    }
+
+    /// A quiet move that caused a cutoff is likely to do so again in sibling
+    /// positions, so it is tried early there.
+    fn remember_quiet_cutoff(&mut self, mv: Move, depth: u32, ply: usize) {
+        if self.killers[ply][0] != Some(mv) {
+            self.killers[ply][1] = self.killers[ply][0];
+            self.killers[ply][0] = Some(mv);
+        }
+        let history = &mut self.history[mv.from.index()][mv.to.index()];
+        *history = (*history + (depth * depth) as i32).min(600_000);
+    }
+
+    fn probe(&self, key: u64) -> Option<TableEntry> {
+        self.table[key as usize % TABLE_SIZE].filter(|entry| entry.key == key)
+    }
+
+    fn store(&mut self, entry: TableEntry) {
+        self.table[entry.key as usize % TABLE_SIZE] = Some(entry);
+    }
+
+    /// The root move followed by the best replies stored in the table.
+    fn principal_variation(&self, game: &Game, depth: u32) -> Vec<Move> {
+        let mut game = game.clone();
+        let mut principal_variation = Vec::new();
+        let mut next = self.root_best_move;
+
+        while let Some(mv) = next
+            && principal_variation.len() < depth as usize
+            && game.generate_legal_moves().contains(&mv)
+        {
+            game.push_move(mv);
+            principal_variation.push(mv);
+            next = self.probe(game.zobrist_key()).and_then(|entry| entry.best_move);
+        }
+        principal_variation
+    }
+}
+
+impl Game {
+    /// The move the engine would play, searching within `limits`. `None` when
+    /// there are no legal moves.
+    pub fn best_move(&self, limits: SearchLimits) -> Option<Move> {
+        Searcher::new(limits).search(self).best_move
+    }
+}
+
+fn is_capture(game: &Game, mv: Move) -> bool {
+    captured_piece(game, mv).is_some()
+}
+
+fn captured_piece(game: &Game, mv: Move) -> Option<PieceType> {
+    let board = game.board();
+    match board.get_piece(mv.to) {
+        Some(piece) => Some(piece.piece_type),
+        None => {
+            let is_pawn = board.get_piece(mv.from).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+            (is_pawn && mv.from.col != mv.to.col).then_some(PieceType::Pawn)
+        }
+    }
+}
+
+/// Mate scores are stored relative to the node rather than the root, so they
+/// stay right when the position is reached at another ply.
+fn score_to_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score + ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score - ply as i32
+    } else {
+        score
+    }
+}
+
+fn score_from_table(score: i32, ply: usize) -> i32 {
+    if score > MATE_SCORE - MAX_PLY as i32 {
+        score - ply as i32
+    } else if score < -MATE_SCORE + MAX_PLY as i32 {
+        score + ply as i32
+    } else {
+        score
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mate_on_the_hundredth_halfmove_beats_the_fifty_move_rule() {
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/8/R3K3 w - - 99 60").unwrap();
+        let result = Searcher::new(SearchLimits::depth(2)).search(&game);
+        assert_eq!(result.best_move.map(|mv| mv.to_uci()), Some("a1a8".to_string()));
+        assert_eq!(result.mate_in(), Some(1));
+    }
+}
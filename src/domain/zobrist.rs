@@ -0,0 +1,172 @@
+use super::{Board, CastlingRights, Color, Game, Piece, PieceType, Position, pawn_attacks};
+
+/// Random keys XORed together to form a position's Zobrist key. They are
+/// generated at compile time from a fixed seed, so keys are the same on every
+/// run and can be stored alongside positions.
+struct ZobristKeys {
+    pieces: [[u64; 64]; 12],
+    black_to_move: u64,
+    castling: [u64; 4],
+    en_passant_file: [u64; 8],
+}
+
+static KEYS: ZobristKeys = ZobristKeys::generate();
+
+impl ZobristKeys {
+    const fn generate() -> Self {
+        let mut state = 0x5EED_C4E5_5B0A_4D21;
+        let mut pieces = [[0; 64]; 12];
+        let mut piece = 0;
+        while piece < 12 {
+            let mut square = 0;
+            while square < 64 {
+                state = splitmix64(state);
+                pieces[piece][square] = mix(state);
+                square += 1;
+            }
+            piece += 1;
+        }
+
+        state = splitmix64(state);
+        let black_to_move = mix(state);
+
+        let mut castling = [0; 4];
+        let mut i = 0;
+        while i < 4 {
+            state = splitmix64(state);
+            castling[i] = mix(state);
+            i += 1;
+        }
+
+        let mut en_passant_file = [0; 8];
+        let mut file = 0;
+        while file < 8 {
+            state = splitmix64(state);
+            en_passant_file[file] = mix(state);
+            file += 1;
+        }
+
+        ZobristKeys { pieces, black_to_move, castling, en_passant_file }
+    }
+}
+
+const fn splitmix64(state: u64) -> u64 {
+    state.wrapping_add(0x9E37_79B9_7F4A_7C15)
+}
+
+const fn mix(state: u64) -> u64 {
+    let mut z = state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
+
+pub(super) fn piece_key(piece: Piece, square: usize) -> u64 {
+    KEYS.pieces[piece.color as usize * 6 + piece.piece_type as usize][square]
+}
+
+fn castling_key(rights: CastlingRights) -> u64 {
+    [rights.white_kingside, rights.white_queenside, rights.black_kingside, rights.black_queenside]
+        .into_iter()
+        .zip(KEYS.castling)
+        .filter(|&(held, _)| held)
+        .fold(0, |key, (_, right_key)| key ^ right_key)
+}
+
+impl Board {
+    /// The key of the pieces built from scratch, for a new position and to
+    /// check the one `set_piece` keeps up to date against.
+    pub(super) fn compute_zobrist_key(&self) -> u64 {
+        let mut key = 0;
+        for (row, rank) in self.get_state().iter().enumerate() {
+            for (col, square) in rank.iter().enumerate() {
+                if let Some(piece) = square {
+                    key ^= piece_key(*piece, Position { row, col }.index());
+                }
+            }
+        }
+        key
+    }
+}
+
+impl Game {
+    /// The key built from scratch, to set up a new position and to check the
+    /// one moves keep up to date against.
+    pub(super) fn compute_zobrist_key(&self) -> u64 {
+        self.board().compute_zobrist_key() ^ self.state_key()
+    }
+
+    /// The part of the key that isn't about the pieces. Moves update the key
+    /// by swapping this part and the pieces that changed squares.
+    pub(super) fn state_key(&self) -> u64 {
+        let mut key = castling_key(self.castling_rights()) ^ self.en_passant_key();
+        if self.current_player() == Color::Black {
+            key ^= KEYS.black_to_move;
+        }
+        key
+    }
+
+    pub(super) fn en_passant_key(&self) -> u64 {
+        let Some(target) = self.en_passant_target() else {
+            return 0;
+        };
+
+        let us = self.current_player();
+        let capturers = pawn_attacks(us.opposite(), target.index()) & self.board().pieces(PieceType::Pawn, us);
+        if capturers == 0 { 0 } else { KEYS.en_passant_file[target.col] }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn play(game: &mut Game, moves: &[&str]) {
+        for uci in moves {
+            game.apply_uci_move(uci).unwrap();
+        }
+    }
+
+    #[test]
+    fn transpositions_share_a_key() {
+        let mut first = Game::new();
+        play(&mut first, &["g1f3", "g8f6", "b1c3", "b8c6"]);
+        let mut second = Game::new();
+        play(&mut second, &["b1c3", "b8c6", "g1f3", "g8f6"]);
+
+        assert_eq!(first.zobrist_key(), second.zobrist_key());
+        assert_ne!(first.zobrist_key(), Game::new().zobrist_key());
+    }
+
+    #[test]
+    fn undo_and_redo_restore_the_key() {
+        let mut game = Game::new();
+        let start = game.zobrist_key();
+        // Castling, a double push and an en passant capture all touch more
+        // than the moving piece.
+        play(&mut game, &["e2e4", "g8f6", "e4e5", "d7d5", "e5d6", "e7e6", "g1f3", "f8e7", "f1e2", "e8g8"]);
+        let end = game.zobrist_key();
+
+        while game.undo_move() {}
+        assert_eq!(game.zobrist_key(), start);
+
+        while game.redo_move() {}
+        assert_eq!(game.zobrist_key(), end);
+    }
+
+    #[test]
+    fn en_passant_file_counts_only_when_a_pawn_can_capture() {
+        // Nothing stands next to e4, so the target makes no difference.
+        let mut game = Game::new();
+        play(&mut game, &["e2e4"]);
+        let without_target = Game::from_fen("rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_eq!(game.zobrist_key(), without_target.zobrist_key());
+
+        // The pawn on e5 can take f5 en passant.
+        play(&mut game, &["a7a6", "e4e5", "f7f5"]);
+        let without_target = Game::from_fen("rnbqkbnr/1pppp1pp/p7/4Pp2/8/8/PPPP1PPP/RNBQKBNR w KQkq - 0 3").unwrap();
+        assert_ne!(game.zobrist_key(), without_target.zobrist_key());
+        let with_target = Game::from_fen("rnbqkbnr/1pppp1pp/p7/4Pp2/8/8/PPPP1PPP/RNBQKBNR w KQkq f6 0 3").unwrap();
+        assert_eq!(game.zobrist_key(), with_target.zobrist_key());
+    }
+}
@@ -0,0 +1,10 @@
+//! Chess rules engine behind the Rusty Chess GUI.
+//!
+//! The `domain` module has no graphics dependencies, so it can be used from
+//! servers and tools with `default-features = false`. `protocol` speaks the
+//! text protocols chess GUIs use to talk to engines.
+
+pub mod domain;
+pub mod protocol;
+
+pub use domain::{Board, Color, Game, GameStatus, Move, MoveError, MoveRecord, Piece, PieceType, Position};
@@ -1,4 +1,3 @@
-mod domain;
 mod presentation;
 
 use macroquad::prelude::*;
@@ -7,9 +6,10 @@ use presentation::ChessUI;
 fn window_conf() -> Conf {
     Conf {
         window_title: "Rusty Chess".to_owned(),
-        window_width: 800,
+        window_width: 1040,
         window_height: 800,
-        window_resizable: false,
+        window_resizable: true,
+        high_dpi: true,
         ..Default::default()
     }
 }
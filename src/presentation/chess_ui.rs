@@ -1,17 +1,215 @@
-use crate::domain::{Color, Game, Piece, PieceType, Position};
+use rusty_chess::domain::{Clock, Color, Game, GameStatus, Move, MoveError, PgnGame, PgnTags, Piece, PieceType, Position, SearchLimits, SearchResult, Searcher, TimeBonus, TimeControl, TimePeriod, parse_pgn};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use macroquad::miniquad::window::{clipboard_get, clipboard_set};
 use macroquad::prelude::*;
 
+// The screen is laid out for a window of this size and `Layout` scales it to
+// fit the actual one. Sizes and positions below are in these design units.
+const DESIGN_WIDTH: f32 = 1040.0;
+const DESIGN_HEIGHT: f32 = 800.0;
+
 const BOARD_SIZE: f32 = 640.0;
-const SQUARE_SIZE: f32 = BOARD_SIZE / 8.0;
 const BOARD_OFFSET_X: f32 = 50.0;
 const BOARD_OFFSET_Y: f32 = 50.0;
 
+/// How long feedback messages stay on screen, in seconds.
+const MESSAGE_DURATION: f64 = 3.0;
+
+/// How long the computer thinks about each move, or less when its clock runs
+/// low.
+const ENGINE_MOVE_TIME: Duration = Duration::from_secs(1);
+
+/// Size of each player's clock, drawn to the right of the board.
+const CLOCK_WIDTH: f32 = 90.0;
+const CLOCK_HEIGHT: f32 = 40.0;
+
+/// Buttons to the right of the board, between the clocks.
+const BUTTON_WIDTH: f32 = CLOCK_WIDTH;
+const BUTTON_HEIGHT: f32 = 30.0;
+
+/// The move list to the right of the clocks, as tall as the board.
+const MOVE_LIST_WIDTH: f32 = 220.0;
+const MOVE_LIST_ROW_HEIGHT: f32 = 24.0;
+const MOVE_LIST_ROWS: usize = (BOARD_SIZE / MOVE_LIST_ROW_HEIGHT) as usize;
+const MOVE_LIST_NUMBER_WIDTH: f32 = 50.0;
+const MOVE_LIST_SAN_WIDTH: f32 = 80.0;
+
+const PROMOTION_CHOICES: [PieceType; 4] = [
+    PieceType::Queen,
+    PieceType::Knight,
+    PieceType::Rook,
+    PieceType::Bishop,
+];
+
+const TIME_CONTROL_PRESETS: usize = 6;
+
+/// Time controls the T key cycles through. Index 0 plays without a clock.
+fn time_control_preset(index: usize) -> Option<TimeControl> {
+    let minutes = |minutes: u64| Duration::from_secs(minutes * 60);
+    match index {
+        1 => Some(TimeControl::sudden_death(minutes(5))),
+        2 => Some(TimeControl::increment(minutes(3), Duration::from_secs(2))),
+        3 => Some(TimeControl::increment(minutes(10), Duration::from_secs(5))),
+        4 => Some(TimeControl::delay(minutes(15), Duration::from_secs(10))),
+        5 => Some(TimeControl::new(vec![
+            TimePeriod { moves: Some(40), time: minutes(90), bonus: TimeBonus::Increment(Duration::from_secs(30)) },
+            TimePeriod { moves: None, time: minutes(30), bonus: TimeBonus::Increment(Duration::from_secs(30)) },
+        ])),
+        _ => None,
+    }
+}
+
+/// Where the file letters and rank numbers are drawn.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CoordinateLabels {
+    /// Outside the board, below it and to its left.
+    Margin,
+    /// In the corners of the bottom row and left column squares.
+    Inside,
+    Hidden,
+}
+
+/// Where everything goes on screen this frame: the design layout scaled
+/// uniformly to the largest size that fits the window, and centred in the
+/// direction that has room to spare.
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    scale: f32,
+    origin: Vec2,
+}
+
+impl Layout {
+    fn for_window(width: f32, height: f32) -> Self {
+        let scale = (width / DESIGN_WIDTH).min(height / DESIGN_HEIGHT);
+        let origin = Vec2::new(width - DESIGN_WIDTH * scale, height - DESIGN_HEIGHT * scale) / 2.0;
+        Layout { scale, origin }
+    }
+
+    /// A length in design units on screen.
+    fn size(&self, length: f32) -> f32 {
+        length * self.scale
+    }
+
+    fn x(&self, x: f32) -> f32 {
+        self.origin.x + x * self.scale
+    }
+
+    fn y(&self, y: f32) -> f32 {
+        self.origin.y + y * self.scale
+    }
+
+    fn rect(&self, x: f32, y: f32, width: f32, height: f32) -> Rect {
+        Rect::new(self.x(x), self.y(y), self.size(width), self.size(height))
+    }
+
+    fn board(&self) -> Rect {
+        self.rect(BOARD_OFFSET_X, BOARD_OFFSET_Y, BOARD_SIZE, BOARD_SIZE)
+    }
+
+    fn square_size(&self) -> f32 {
+        self.size(BOARD_SIZE / 8.0)
+    }
+
+    /// The clock next to the top or bottom edge of the board.
+    fn clock(&self, top: bool) -> Rect {
+        let y = if top { BOARD_OFFSET_Y } else { BOARD_OFFSET_Y + BOARD_SIZE - CLOCK_HEIGHT };
+        self.rect(BOARD_OFFSET_X + BOARD_SIZE + 8.0, y, CLOCK_WIDTH, CLOCK_HEIGHT)
+    }
+
+    fn flip_button(&self) -> Rect {
+        let y = BOARD_OFFSET_Y + BOARD_SIZE / 2.0 - BUTTON_HEIGHT - 5.0;
+        self.rect(BOARD_OFFSET_X + BOARD_SIZE + 8.0, y, BUTTON_WIDTH, BUTTON_HEIGHT)
+    }
+
+    fn auto_orient_button(&self) -> Rect {
+        let y = BOARD_OFFSET_Y + BOARD_SIZE / 2.0 + 5.0;
+        self.rect(BOARD_OFFSET_X + BOARD_SIZE + 8.0, y, BUTTON_WIDTH, BUTTON_HEIGHT)
+    }
+
+    fn move_list(&self) -> Rect {
+        self.rect(BOARD_OFFSET_X + BOARD_SIZE + CLOCK_WIDTH + 20.0, BOARD_OFFSET_Y, MOVE_LIST_WIDTH, BOARD_SIZE)
+    }
+}
+
+/// SAN of the game's recorded line for the move list, rebuilt only when the
+/// line changes.
+#[derive(Debug, Default)]
+struct MoveList {
+    moves: Vec<Move>,
+    san: Vec<String>,
+    /// Fullmove number of the first move.
+    first_move_number: u32,
+    /// Whether the line starts with a Black move, leaving White's half of the
+    /// first row empty.
+    black_starts: bool,
+}
+
+impl MoveList {
+    fn row_count(&self) -> usize {
+        (self.san.len() + self.black_starts as usize).div_ceil(2)
+    }
+
+    /// Row and column (0 for White, 1 for Black) of the move at `ply`.
+    fn cell(&self, ply: usize) -> (usize, usize) {
+        let slot = ply + self.black_starts as usize;
+        (slot / 2, slot % 2)
+    }
+}
+
+/// A search running on a background thread, with the position it was started
+/// from so that a result for a position that is no longer on the board can be
+/// thrown away.
+struct EngineSearch {
+    zobrist_key: u64,
+    moves_played: usize,
+    stop: Arc<AtomicBool>,
+    result: Receiver<SearchResult>,
+}
+
 pub struct ChessUI {
     game: Game,
+    /// Recalculated at the start of every frame, before input is mapped to
+    /// the board.
+    layout: Layout,
     selected_square: Option<Position>,
     dragging_piece: Option<Position>,
     drag_offset: (f32, f32),
     pieces_texture: Texture2D,
+    /// Source and destination of a pawn move waiting for the promotion piece.
+    pending_promotion: Option<(Position, Position)>,
+    /// Games from the last PGN file dropped on the window.
+    loaded_games: Vec<PgnGame>,
+    loaded_game_index: usize,
+    /// Text typed into the SAN move box; `None` while the box isn't focused.
+    move_input: Option<String>,
+    /// Feedback for the player and the time it was shown.
+    message: Option<(String, f64)>,
+    /// Side played by the computer, if any.
+    engine_color: Option<Color>,
+    engine_search: Option<EngineSearch>,
+    /// Whether Black is shown at the bottom of the board.
+    flipped: bool,
+    /// Turns the board so that the human player sits at the bottom whenever
+    /// the computer plays one side.
+    auto_orient: bool,
+    coordinate_labels: CoordinateLabels,
+    move_list: MoveList,
+    /// First row of the move list in view.
+    move_list_scroll: usize,
+    /// Ply the move list last scrolled to, to follow the game as it moves on.
+    move_list_ply: usize,
+    /// Preset of `time_control_preset` in use.
+    time_control_index: usize,
+    clock: Option<Clock>,
+    /// Ply of the recorded line the clock has been pressed up to. While the
+    /// board shows any other position the clock is paused.
+    clock_ply: usize,
+    /// Whether the clock was stopped only because the player browsed away
+    /// from `clock_ply`.
+    clock_paused: bool,
 }
 
 impl ChessUI {
@@ -25,15 +223,34 @@ impl ChessUI {
 
         ChessUI {
             game: Game::new(),
+            layout: Layout::for_window(screen_width(), screen_height()),
             selected_square: None,
             dragging_piece: None,
             drag_offset: (0.0, 0.0),
             pieces_texture: _pieces_texture,
+            pending_promotion: None,
+            loaded_games: Vec::new(),
+            loaded_game_index: 0,
+            move_input: None,
+            message: None,
+            engine_color: None,
+            engine_search: None,
+            flipped: false,
+            auto_orient: true,
+            coordinate_labels: CoordinateLabels::Margin,
+            move_list: MoveList::default(),
+            move_list_scroll: 0,
+            move_list_ply: 0,
+            time_control_index: 0,
+            clock: None,
+            clock_ply: 0,
+            clock_paused: false,
         }
     }
 
     pub async fn run(&mut self) {
         loop {
+            self.layout = Layout::for_window(screen_width(), screen_height());
             self.handle_input().await;
             self.draw();
             next_frame().await;
@@ -41,6 +258,581 @@ impl ChessUI {
     }
 
     async fn handle_input(&mut self) {
+        self.update_clock();
+        self.update_engine();
+        self.update_orientation();
+        self.update_move_list();
+
+        if self.move_input.is_some() {
+            self.handle_move_input();
+            return;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let mouse = Vec2::from(mouse_position());
+            if self.layout.flip_button().contains(mouse) {
+                self.flip_board();
+            } else if self.layout.auto_orient_button().contains(mouse) {
+                self.toggle_auto_orient();
+            } else if let Some(ply) = self.move_list_ply_at(mouse) {
+                self.go_to_ply(ply);
+            }
+        }
+
+        let (_, wheel_y) = mouse_wheel();
+        if wheel_y != 0.0 && self.layout.move_list().contains(Vec2::from(mouse_position())) {
+            let max_scroll = self.move_list.row_count().saturating_sub(MOVE_LIST_ROWS);
+            self.move_list_scroll = if wheel_y > 0.0 {
+                self.move_list_scroll.saturating_sub(1)
+            } else {
+                (self.move_list_scroll + 1).min(max_scroll)
+            };
+        }
+
+        if is_key_pressed(KeyCode::Left) {
+            self.go_to_ply(self.game.current_ply().saturating_sub(1));
+        }
+
+        if is_key_pressed(KeyCode::Right) {
+            self.go_to_ply(self.game.current_ply() + 1);
+        }
+
+        if is_key_pressed(KeyCode::Home) {
+            self.go_to_ply(0);
+        }
+
+        if is_key_pressed(KeyCode::End) {
+            self.go_to_ply(self.game.recorded_moves().len());
+        }
+
+        if self.game.status().is_game_over() {
+            self.selected_square = None;
+            self.dragging_piece = None;
+            self.pending_promotion = None;
+        } else if self.is_engine_turn() {
+            self.selected_square = None;
+            self.dragging_piece = None;
+        } else if self.pending_promotion.is_some() {
+            self.handle_promotion_input();
+        } else {
+            self.handle_mouse_input();
+        }
+
+        if is_key_pressed(KeyCode::Enter) && self.pending_promotion.is_none() && !self.is_engine_turn() {
+            // Drop characters typed before the box was opened.
+            while get_char_pressed().is_some() {}
+            self.move_input = Some(String::new());
+            return;
+        }
+
+        if is_key_pressed(KeyCode::U) && !self.undo_move() {
+            self.show_message("Nothing to undo");
+        }
+
+        if is_key_pressed(KeyCode::E) {
+            self.switch_engine_side();
+        }
+
+        if is_key_pressed(KeyCode::F) {
+            self.flip_board();
+        }
+
+        if is_key_pressed(KeyCode::A) {
+            self.toggle_auto_orient();
+        }
+
+        if is_key_pressed(KeyCode::L) {
+            self.coordinate_labels = match self.coordinate_labels {
+                CoordinateLabels::Margin => CoordinateLabels::Inside,
+                CoordinateLabels::Inside => CoordinateLabels::Hidden,
+                CoordinateLabels::Hidden => CoordinateLabels::Margin,
+            };
+        }
+
+        if is_key_pressed(KeyCode::T) {
+            self.switch_time_control();
+        }
+
+        if is_key_pressed(KeyCode::D) && let Err(error) = self.game.claim_draw() {
+            self.show_message(error.to_string());
+        }
+
+        if is_key_pressed(KeyCode::R) && is_key_down(KeyCode::LeftControl) {
+            let clock_follows = self.game.current_ply() == self.clock_ply;
+            if self.game.redo_move() {
+                if clock_follows {
+                    self.move_clock_to(self.game.current_ply());
+                }
+            } else {
+                self.show_message("Nothing to redo");
+            }
+        }
+
+        if is_key_pressed(KeyCode::N) && is_key_down(KeyCode::LeftControl) {
+            self.game.restart();
+            self.selected_square = None;
+            self.dragging_piece = None;
+            self.pending_promotion = None;
+            self.reset_clock();
+        }
+
+        if is_key_pressed(KeyCode::C) && is_key_down(KeyCode::LeftControl) {
+            clipboard_set(&self.game.to_fen());
+            self.show_message("Copied FEN to clipboard");
+        }
+
+        if is_key_pressed(KeyCode::V) && is_key_down(KeyCode::LeftControl) {
+            self.paste_fen();
+        }
+
+        if is_key_pressed(KeyCode::S) && is_key_down(KeyCode::LeftControl) {
+            self.save_pgn();
+        }
+
+        if let Some(file) = get_dropped_files().into_iter().next() {
+            self.open_pgn(file.bytes.unwrap_or_default());
+        }
+
+        if is_key_pressed(KeyCode::PageDown) && self.loaded_game_index + 1 < self.loaded_games.len() {
+            self.show_loaded_game(self.loaded_game_index + 1);
+        }
+
+        if is_key_pressed(KeyCode::PageUp) && self.loaded_game_index > 0 {
+            self.show_loaded_game(self.loaded_game_index - 1);
+        }
+    }
+
+    fn handle_move_input(&mut self) {
+        let Some(input) = self.move_input.as_mut() else {
+            return;
+        };
+
+        while let Some(c) = get_char_pressed() {
+            if c.is_ascii_graphic() && input.len() < 12 {
+                input.push(c);
+            }
+        }
+
+        if is_key_pressed(KeyCode::Backspace) {
+            input.pop();
+        }
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.move_input = None;
+        } else if is_key_pressed(KeyCode::Enter) {
+            let san = self.move_input.take().unwrap_or_default();
+            if san.is_empty() {
+                return;
+            }
+
+            let result = match self.game.parse_san(&san) {
+                Ok(mv) => self.play_move(mv).map_err(|error| error.to_string()),
+                Err(error) => Err(error.to_string()),
+            };
+            if let Err(error) = result {
+                self.show_message(format!("Invalid move: {}", error));
+            }
+            self.selected_square = None;
+        }
+    }
+
+    fn is_engine_turn(&self) -> bool {
+        self.engine_color == Some(self.game.current_player()) && !self.game.status().is_game_over()
+    }
+
+    /// Cycles between playing White against the computer, playing Black
+    /// against it and two human players.
+    fn switch_engine_side(&mut self) {
+        self.engine_color = match self.engine_color {
+            None => Some(Color::Black),
+            Some(Color::Black) => Some(Color::White),
+            Some(Color::White) => None,
+        };
+        self.show_message(match self.engine_color {
+            Some(Color::Black) => "You play White against the computer",
+            Some(Color::White) => "You play Black against the computer",
+            None => "Two players",
+        });
+    }
+
+    /// Turning the board by hand switches automatic orientation off, which
+    /// would otherwise turn it straight back.
+    fn flip_board(&mut self) {
+        self.flipped = !self.flipped;
+        if self.auto_orient && self.engine_color.is_some() {
+            self.auto_orient = false;
+            self.show_message("Automatic orientation off");
+        }
+    }
+
+    fn toggle_auto_orient(&mut self) {
+        self.auto_orient = !self.auto_orient;
+        self.show_message(if self.auto_orient {
+            "Automatic orientation on"
+        } else {
+            "Automatic orientation off"
+        });
+    }
+
+    /// With automatic orientation, puts the side the computer doesn't play at
+    /// the bottom. Games between two players keep the board as it is.
+    fn update_orientation(&mut self) {
+        if self.auto_orient
+            && let Some(engine_color) = self.engine_color
+        {
+            self.flipped = engine_color == Color::White;
+        }
+    }
+
+    /// Against the computer, also takes back its reply so that it is the
+    /// player's turn again. The clock goes back with the moves unless the
+    /// player was browsing an earlier position.
+    fn undo_move(&mut self) -> bool {
+        let clock_follows = self.game.current_ply() == self.clock_ply;
+        if !self.game.undo_move() {
+            return false;
+        }
+        if self.is_engine_turn() {
+            self.game.undo_move();
+        }
+        if clock_follows {
+            self.move_clock_to(self.game.current_ply());
+        }
+        true
+    }
+
+    /// Shows the position after `ply` moves of the recorded line. The clock
+    /// is left alone, and `update_clock` pauses it until the player returns.
+    fn go_to_ply(&mut self, ply: usize) {
+        if ply == self.game.current_ply() || !self.game.go_to_ply(ply) {
+            return;
+        }
+
+        self.selected_square = None;
+        self.dragging_piece = None;
+        self.pending_promotion = None;
+    }
+
+    /// Rebuilds the move list when the recorded line changed, and scrolls it
+    /// to keep the current move in view when that moved.
+    fn update_move_list(&mut self) {
+        let moves: Vec<Move> = self.game.recorded_moves().iter().map(|record| record.as_move()).collect();
+        if moves != self.move_list.moves {
+            let mut start = self.game.clone();
+            start.go_to_ply(0);
+            self.move_list = MoveList {
+                moves,
+                san: self.game.recorded_san(),
+                first_move_number: start.fullmove_number(),
+                black_starts: start.current_player() == Color::Black,
+            };
+            self.move_list_scroll = 0;
+            self.move_list_ply = usize::MAX;
+        }
+
+        let ply = self.game.current_ply();
+        if ply != self.move_list_ply {
+            self.move_list_ply = ply;
+            let (row, _) = self.move_list.cell(ply.saturating_sub(1));
+            if row < self.move_list_scroll {
+                self.move_list_scroll = row;
+            } else if row >= self.move_list_scroll + MOVE_LIST_ROWS {
+                self.move_list_scroll = row + 1 - MOVE_LIST_ROWS;
+            }
+        }
+    }
+
+    /// The ply reached by clicking the move under `point` in the move list.
+    fn move_list_ply_at(&self, point: Vec2) -> Option<usize> {
+        let rect = self.layout.move_list();
+        if !rect.contains(point) {
+            return None;
+        }
+
+        // Back to design units, which the row and column sizes are given in.
+        let row = ((point.y - rect.y) / self.layout.size(MOVE_LIST_ROW_HEIGHT)) as usize + self.move_list_scroll;
+        let column = match (point.x - rect.x) / self.layout.scale {
+            x if (MOVE_LIST_NUMBER_WIDTH..MOVE_LIST_NUMBER_WIDTH + MOVE_LIST_SAN_WIDTH).contains(&x) => 0,
+            x if x >= MOVE_LIST_NUMBER_WIDTH + MOVE_LIST_SAN_WIDTH => 1,
+            _ => return None,
+        };
+
+        let ply = (row * 2 + column).checked_sub(self.move_list.black_starts as usize)?;
+        (ply < self.move_list.san.len()).then_some(ply + 1)
+    }
+
+    /// Plays a move on the board and hands the turn over on the clock.
+    fn play_move(&mut self, mv: Move) -> Result<(), MoveError> {
+        let ply = self.game.current_ply();
+        self.game.make_move(mv)?;
+        // A move played while browsing replaces the rest of the line, so the
+        // clock first returns to the position the move was played from.
+        self.move_clock_to(ply);
+        self.press_clock();
+        Ok(())
+    }
+
+    /// Takes back or presses the clock once per move until it stands at `ply`.
+    fn move_clock_to(&mut self, ply: usize) {
+        while self.clock_ply > ply {
+            self.take_back_clock();
+        }
+        while self.clock_ply < ply {
+            self.press_clock();
+        }
+    }
+
+    fn press_clock(&mut self) {
+        self.clock_ply += 1;
+        if let Some(clock) = &mut self.clock {
+            clock.press(Instant::now());
+        }
+    }
+
+    fn take_back_clock(&mut self) {
+        self.clock_ply = self.clock_ply.saturating_sub(1);
+        let now = Instant::now();
+        if let Some(clock) = &mut self.clock
+            && !clock.take_back(now)
+        {
+            // The clock was set up after this move, so there is no time to
+            // give back; the turn just changes sides.
+            clock.switch_turn(now);
+        }
+    }
+
+    /// Gives both players the full time of the current time control, from the
+    /// position on the board.
+    fn reset_clock(&mut self) {
+        self.clock = time_control_preset(self.time_control_index)
+            .map(|control| Clock::for_game(control, &self.game));
+        self.clock_ply = self.game.current_ply();
+        self.clock_paused = false;
+    }
+
+    fn switch_time_control(&mut self) {
+        self.time_control_index = (self.time_control_index + 1) % TIME_CONTROL_PRESETS;
+        self.reset_clock();
+        let message = match &self.clock {
+            Some(clock) => format!("Clock: {}, starting with the next move", clock.control().description()),
+            None => "No clock".to_string(),
+        };
+        self.show_message(message);
+    }
+
+    /// Ends the game when the player to move runs out of time, and stops the
+    /// clock once the game is over or while the player browses other moves.
+    fn update_clock(&mut self) {
+        let Some(clock) = &mut self.clock else {
+            return;
+        };
+
+        let now = Instant::now();
+        if self.game.current_ply() != self.clock_ply {
+            if clock.is_running() {
+                clock.stop(now);
+                self.clock_paused = true;
+            }
+            return;
+        }
+        if self.clock_paused {
+            clock.resume(now);
+            self.clock_paused = false;
+        }
+
+        if self.game.status().is_game_over() {
+            if clock.is_running() {
+                clock.stop(now);
+            }
+        } else if let Some(flagged) = clock.flagged(now) {
+            clock.stop(now);
+            self.game.lose_on_time(flagged);
+        }
+    }
+
+    /// Starts a search when it is the computer's turn and plays its move once
+    /// the search is done. A search for a position that was left in the
+    /// meantime, by undoing or loading another game, is stopped and ignored.
+    fn update_engine(&mut self) {
+        let Some(search) = &self.engine_search else {
+            // While the player browses back through the game the computer
+            // waits, so that it doesn't replace the moves after this one.
+            let at_end_of_line = self.game.current_ply() == self.game.recorded_moves().len();
+            if self.is_engine_turn() && at_end_of_line {
+                self.start_engine_search();
+            }
+            return;
+        };
+
+        let still_current = self.is_engine_turn()
+            && search.zobrist_key == self.game.zobrist_key()
+            && search.moves_played == self.game.move_history().len();
+        if !still_current {
+            search.stop.store(true, Ordering::Relaxed);
+            self.engine_search = None;
+            return;
+        }
+
+        match search.result.try_recv() {
+            Ok(result) => {
+                self.engine_search = None;
+                if let Some(mv) = result.best_move
+                    && let Err(error) = self.play_move(mv)
+                {
+                    self.show_message(format!("Engine move failed: {}", error));
+                }
+            }
+            Err(TryRecvError::Empty) => {}
+            Err(TryRecvError::Disconnected) => {
+                self.engine_search = None;
+                self.engine_color = None;
+                self.show_message("The engine stopped unexpectedly");
+            }
+        }
+    }
+
+    fn start_engine_search(&mut self) {
+        let move_time = match &self.clock {
+            Some(clock) => ENGINE_MOVE_TIME.min(clock.remaining(self.game.current_player(), Instant::now()) / 30),
+            None => ENGINE_MOVE_TIME,
+        };
+        let mut searcher = Searcher::new(SearchLimits::move_time(move_time));
+        let stop = searcher.stop_flag();
+        let game = self.game.clone();
+        let (sender, result) = mpsc::channel();
+
+        std::thread::spawn(move || {
+            let _ = sender.send(searcher.search(&game));
+        });
+
+        self.engine_search = Some(EngineSearch {
+            zobrist_key: self.game.zobrist_key(),
+            moves_played: self.game.move_history().len(),
+            stop,
+            result,
+        });
+    }
+
+    fn show_message(&mut self, text: impl Into<String>) {
+        self.message = Some((text.into(), get_time()));
+    }
+
+    fn open_pgn(&mut self, bytes: Vec<u8>) {
+        let text = String::from_utf8_lossy(&bytes);
+        match parse_pgn(&text) {
+            Ok(games) => {
+                self.show_message(format!("Loaded {} game(s), use the arrow keys to step through the moves", games.len()));
+                self.loaded_games = games;
+                self.show_loaded_game(0);
+            }
+            Err(error) => self.show_message(format!("Invalid PGN: {}", error)),
+        }
+    }
+
+    /// Shows a loaded game from its first move, so that redo steps through it.
+    fn show_loaded_game(&mut self, index: usize) {
+        let Some(pgn_game) = self.loaded_games.get(index) else {
+            return;
+        };
+
+        let mut game = pgn_game.game.clone();
+        while game.undo_move() {}
+
+        self.loaded_game_index = index;
+        self.load_game(game);
+    }
+
+    fn load_game(&mut self, game: Game) {
+        self.game = game;
+        self.selected_square = None;
+        self.dragging_piece = None;
+        self.pending_promotion = None;
+        self.reset_clock();
+    }
+
+    fn save_pgn(&mut self) {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or(0);
+        let (year, month, day) = civil_date_from_unix(seconds);
+
+        let tags = PgnTags {
+            event: "Rusty Chess game".to_string(),
+            site: "Rusty Chess".to_string(),
+            date: format!("{:04}.{:02}.{:02}", year, month, day),
+            ..PgnTags::default()
+        };
+
+        let path = format!("rusty_chess_{}.pgn", seconds);
+        let pgn = match &self.clock {
+            Some(clock) => self.game.to_pgn_with_clock(&tags, clock),
+            None => self.game.to_pgn_with_tags(&tags),
+        };
+        match std::fs::write(&path, pgn) {
+            Ok(()) => self.show_message(format!("Saved game to {}", path)),
+            Err(error) => self.show_message(format!("Could not save {}: {}", path, error)),
+        }
+    }
+
+    fn paste_fen(&mut self) {
+        let Some(fen) = clipboard_get() else {
+            self.show_message("Clipboard is empty");
+            return;
+        };
+
+        match Game::from_fen(fen.trim()) {
+            Ok(game) => self.load_game(game),
+            Err(error) => self.show_message(format!("Invalid FEN: {}", error)),
+        }
+    }
+
+    fn handle_promotion_input(&mut self) {
+        let Some((from, to)) = self.pending_promotion else {
+            return;
+        };
+
+        if is_key_pressed(KeyCode::Escape) {
+            self.pending_promotion = None;
+            return;
+        }
+
+        if is_mouse_button_pressed(MouseButton::Left) {
+            let (mouse_x, mouse_y) = mouse_position();
+            let clicked = self.screen_to_board_position(mouse_x, mouse_y);
+
+            let choice = self
+                .promotion_picker_squares()
+                .into_iter()
+                .find(|&(_, pos)| Some(pos) == clicked);
+
+            if let Some((piece_type, _)) = choice
+                && let Err(error) = self.play_move(Move::with_promotion(from, to, piece_type))
+            {
+                self.show_message(format!("Invalid move: {}", error));
+            }
+
+            self.pending_promotion = None;
+        }
+    }
+
+    fn try_move(&mut self, from: Position, to: Position) {
+        if self.game.is_promotion_move(from, to) {
+            // Only open the picker for moves that would actually be legal.
+            let mut preview = self.game.clone();
+            match preview.make_move(Move::with_promotion(from, to, PieceType::Queen)) {
+                Ok(()) => self.pending_promotion = Some((from, to)),
+                Err(error) => self.show_message(format!("Invalid move: {}", error)),
+            }
+            return;
+        }
+
+        if let Err(error) = self.play_move(Move::new(from, to)) {
+            self.show_message(format!("Invalid move: {}", error));
+        }
+    }
+
+    fn handle_mouse_input(&mut self) {
         if is_mouse_button_pressed(MouseButton::Left) {
             let (mouse_x, mouse_y) = mouse_position();
 
@@ -53,46 +845,24 @@ impl ChessUI {
                         self.drag_offset = (mouse_x - board_x, mouse_y - board_y);
                     }
                 } else if let Some(selected) = self.selected_square {
-                    if let Err(error) = self.game.make_move(selected, pos) {
-                        println!("Invalid move: {}", error);
-                    }
+                    self.try_move(selected, pos);
                     self.selected_square = None;
                     self.dragging_piece = None;
                 }
             }
         }
 
-        if is_mouse_button_released(MouseButton::Left) {
-            if let Some(drag_pos) = self.dragging_piece {
-                let (mouse_x, mouse_y) = mouse_position();
-
-                if let Some(target_pos) = self.screen_to_board_position(mouse_x, mouse_y) {
-                    if let Err(error) = self.game.make_move(drag_pos, target_pos) {
-                        println!("Invalid move: {}", error);
-                    }
-                }
-
-                self.dragging_piece = None;
-                self.selected_square = None;
-            }
-        }
-
-        if is_key_pressed(KeyCode::U) {
-            if !self.game.undo_move() {
-                println!("Nothing to undo");
-            }
-        }
+        if is_mouse_button_released(MouseButton::Left)
+            && let Some(drag_pos) = self.dragging_piece
+        {
+            let (mouse_x, mouse_y) = mouse_position();
 
-        if is_key_pressed(KeyCode::R) && is_key_down(KeyCode::LeftControl) {
-            if !self.game.redo_move() {
-                println!("Nothing to redo");
+            if let Some(target_pos) = self.screen_to_board_position(mouse_x, mouse_y) {
+                self.try_move(drag_pos, target_pos);
             }
-        }
 
-        if is_key_pressed(KeyCode::N) && is_key_down(KeyCode::LeftControl) {
-            self.game.restart();
-            self.selected_square = None;
             self.dragging_piece = None;
+            self.selected_square = None;
         }
     }
 
@@ -100,15 +870,29 @@ impl ChessUI {
         clear_background(WHITE);
 
         self.draw_board();
+        self.draw_coordinates();
         self.draw_pieces();
+        self.draw_promotion_picker();
+        self.draw_clocks();
+        self.draw_buttons();
+        self.draw_move_list();
         self.draw_ui_info();
     }
 
     fn draw_board(&self) {
+        let square_size = self.layout.square_size();
+        let last_move = self.game.last_move().map(|record| [record.from, record.to]);
+        let current_player = self.game.current_player();
+        let checked_king = if self.game.is_in_check(current_player) {
+            self.game.board().find_king(current_player)
+        } else {
+            None
+        };
+
         for row in 0..8 {
             for col in 0..8 {
-                let x = BOARD_OFFSET_X + col as f32 * SQUARE_SIZE;
-                let y = BOARD_OFFSET_Y + row as f32 * SQUARE_SIZE;
+                let pos = Position { row, col };
+                let (x, y) = self.board_to_screen_position(pos);
 
                 let is_light_square = (row + col) % 2 == 0;
                 let mut color = if is_light_square {
@@ -117,32 +901,113 @@ impl ChessUI {
                     macroquad::color::Color::from_rgba(76, 57, 59, 255)
                 };
 
-                if let Some(selected) = self.selected_square {
-                    if selected.row == row && selected.col == col {
-                        color = macroquad::color::Color::from_rgba(255, 255, 0, 128);
-                    }
+                if self.selected_square == Some(pos) {
+                    color = macroquad::color::Color::from_rgba(255, 255, 0, 128);
                 }
 
-                draw_rectangle(x, y, SQUARE_SIZE, SQUARE_SIZE, color);
+                draw_rectangle(x, y, square_size, square_size, color);
+
+                if last_move.is_some_and(|squares| squares.contains(&pos)) && self.selected_square != Some(pos) {
+                    draw_rectangle(x, y, square_size, square_size, macroquad::color::Color::from_rgba(205, 210, 106, 110));
+                }
+
+                if checked_king == Some(pos) {
+                    draw_check_glow(x + square_size / 2.0, y + square_size / 2.0, square_size);
+                }
             }
         }
 
-        draw_rectangle_lines(
-            BOARD_OFFSET_X,
-            BOARD_OFFSET_Y,
-            BOARD_SIZE,
-            BOARD_SIZE,
-            2.0,
-            BLACK,
-        );
+        let board = self.layout.board();
+        draw_rectangle_lines(board.x, board.y, board.w, board.h, self.layout.size(2.0), BLACK);
+
+        self.draw_legal_destinations();
+    }
+
+    /// File letters along the bottom edge and rank numbers along the left
+    /// edge as the board is currently turned, sized to the squares.
+    fn draw_coordinates(&self) {
+        let square_size = self.layout.square_size();
+        if self.coordinate_labels == CoordinateLabels::Hidden {
+            return;
+        }
+
+        let board = self.layout.board();
+        let font_size = square_size * 0.22;
+        let (bottom_row, left_col) = if self.flipped { (0, 7) } else { (7, 0) };
+
+        for i in 0..8 {
+            let file_square = Position { row: bottom_row, col: i };
+            let rank_square = Position { row: i, col: left_col };
+            let file_notation = file_square.to_chess_notation();
+            let rank_notation = rank_square.to_chess_notation();
+            let (file, rank) = (&file_notation[..1], &rank_notation[1..]);
+
+            let (file_x, file_y) = self.board_to_screen_position(file_square);
+            let (rank_x, rank_y) = self.board_to_screen_position(rank_square);
+            let file_size = measure_ui_text(file, font_size);
+            let rank_size = measure_ui_text(rank, font_size);
+
+            match self.coordinate_labels {
+                CoordinateLabels::Margin => {
+                    draw_ui_text(
+                        file,
+                        file_x + (square_size - file_size.width) / 2.0,
+                        board.bottom() + file_size.offset_y + self.layout.size(4.0),
+                        font_size,
+                        DARKGRAY,
+                    );
+                    draw_ui_text(
+                        rank,
+                        board.x - rank_size.width - self.layout.size(8.0),
+                        rank_y + (square_size + rank_size.offset_y) / 2.0,
+                        font_size,
+                        DARKGRAY,
+                    );
+                }
+                CoordinateLabels::Inside => {
+                    let color = macroquad::color::Color::from_rgba(215, 190, 150, 255);
+                    let padding = square_size * 0.05;
+                    draw_ui_text(
+                        file,
+                        file_x + square_size - file_size.width - padding,
+                        file_y + square_size - padding,
+                        font_size,
+                        color,
+                    );
+                    draw_ui_text(rank, rank_x + padding, rank_y + rank_size.offset_y + padding, font_size, color);
+                }
+                CoordinateLabels::Hidden => {}
+            }
+        }
+    }
+
+    /// Marks where the selected piece can go: a dot on empty squares and a
+    /// ring around pieces it can capture.
+    fn draw_legal_destinations(&self) {
+        let square_size = self.layout.square_size();
+        let Some(selected) = self.selected_square else {
+            return;
+        };
+
+        let hint_color = macroquad::color::Color::from_rgba(20, 20, 20, 90);
+        for destination in self.game.legal_destinations(selected) {
+            let (x, y) = self.board_to_screen_position(destination);
+            let (center_x, center_y) = (x + square_size / 2.0, y + square_size / 2.0);
+
+            if self.game.board().get_piece(destination).is_some() {
+                draw_circle_lines(center_x, center_y, square_size * 0.45, square_size * 0.07, hint_color);
+            } else {
+                draw_circle(center_x, center_y, square_size * 0.15, hint_color);
+            }
+        }
     }
 
     fn draw_pieces(&self) {
         let board_state = self.game.board().get_state();
 
-        for row in 0..8 {
-            for col in 0..8 {
-                if let Some(piece) = board_state[row][col] {
+        for (row, rank) in board_state.iter().enumerate() {
+            for (col, square) in rank.iter().enumerate() {
+                if let Some(piece) = *square {
                     let pos = Position::new(row, col).unwrap();
 
                     if Some(pos) == self.dragging_piece {
@@ -159,6 +1024,41 @@ impl ChessUI {
         }
     }
 
+    /// The picker covers four squares of the destination file, starting at the
+    /// promotion square and running towards the centre of the board.
+    fn promotion_picker_squares(&self) -> Vec<(PieceType, Position)> {
+        let Some((_, to)) = self.pending_promotion else {
+            return Vec::new();
+        };
+
+        PROMOTION_CHOICES
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &piece_type)| {
+                let row = if to.row == 0 { i } else { to.row - i };
+                Position::new(row, to.col).map(|pos| (piece_type, pos))
+            })
+            .collect()
+    }
+
+    fn draw_promotion_picker(&self) {
+        let square_size = self.layout.square_size();
+        if self.pending_promotion.is_none() {
+            return;
+        }
+        let color = self.game.current_player();
+
+        let board = self.layout.board();
+        draw_rectangle(board.x, board.y, board.w, board.h, macroquad::color::Color::from_rgba(0, 0, 0, 128));
+
+        for (piece_type, pos) in self.promotion_picker_squares() {
+            let (x, y) = self.board_to_screen_position(pos);
+            draw_rectangle(x, y, square_size, square_size, macroquad::color::Color::from_rgba(230, 230, 230, 255));
+            draw_rectangle_lines(x, y, square_size, square_size, self.layout.size(2.0), BLACK);
+            self.draw_piece_at(Piece::new(piece_type, color), x, y);
+        }
+    }
+
     fn get_piece_sprite_coords(&self, piece: Piece) -> (f32, f32) {
         const SPRITE_SIZE: f32 = 16.0;
         
@@ -183,13 +1083,14 @@ impl ChessUI {
         (col as f32 * SPRITE_SIZE, row as f32 * SPRITE_SIZE)
     }
 
-    fn draw_piece_at(&self, piece: crate::domain::Piece, x: f32, y: f32) {
+    fn draw_piece_at(&self, piece: Piece, x: f32, y: f32) {
+        let square_size = self.layout.square_size();
         let (sprite_x, sprite_y) = self.get_piece_sprite_coords(piece);
         const SPRITE_SIZE: f32 = 16.0;
         
         // Calculate piece size (smaller than square to avoid distortion)
-        let piece_size = SQUARE_SIZE * 0.8;
-        let offset = (SQUARE_SIZE - piece_size) / 2.0;
+        let piece_size = square_size * 0.8;
+        let offset = (square_size - piece_size) / 2.0;
 
         draw_texture_ex(
             &self.pieces_texture,
@@ -210,39 +1111,288 @@ impl ChessUI {
             Color::Black => "Black",
         };
 
-        draw_text(
-            &format!("Current player: {}", current_player),
-            BOARD_OFFSET_X,
-            BOARD_OFFSET_Y + BOARD_SIZE + 30.0,
-            24.0,
+        let mut status_text = match self.game.status() {
+            GameStatus::Ongoing => format!("Current player: {}", current_player),
+            GameStatus::Check => format!("Current player: {} - Check!", current_player),
+            GameStatus::Checkmate(winner) => {
+                let winner = match winner {
+                    Color::White => "White",
+                    Color::Black => "Black",
+                };
+                format!("Checkmate! {} wins", winner)
+            }
+            GameStatus::TimeForfeit(winner) => {
+                let winner = match winner {
+                    Color::White => "White",
+                    Color::Black => "Black",
+                };
+                format!("{} wins on time", winner)
+            }
+            GameStatus::Draw(reason) => format!("Draw by {}", reason.description()),
+        };
+        if self.engine_search.is_some() {
+            status_text.push_str(" - Computer is thinking...");
+        }
+
+        draw_ui_text(
+            &status_text,
+            self.layout.x(BOARD_OFFSET_X),
+            self.layout.y(BOARD_OFFSET_Y + BOARD_SIZE + 42.0),
+            self.layout.size(24.0),
             BLACK,
         );
 
-        draw_text(
-            "Controls: U - Undo, Ctrl+R - Redo, Ctrl+N - New Game",
-            BOARD_OFFSET_X,
-            BOARD_OFFSET_Y + BOARD_SIZE + 60.0,
-            20.0,
+        self.draw_move_input();
+
+        // Feedback messages take the line above the board for a few seconds,
+        // otherwise it offers any draw that can be claimed.
+        let message = match &self.message {
+            Some((text, shown_at)) if get_time() - shown_at < MESSAGE_DURATION => Some(text.clone()),
+            _ => self
+                .game
+                .claimable_draw()
+                .map(|reason| format!("Press D to claim a draw by {}", reason.description())),
+        };
+        if let Some(message) = message {
+            draw_ui_text(
+                &message,
+                self.layout.x(BOARD_OFFSET_X),
+                self.layout.y(BOARD_OFFSET_Y - 15.0),
+                self.layout.size(22.0),
+                macroquad::color::Color::from_rgba(160, 40, 40, 255),
+            );
+        }
+
+        draw_ui_text(
+            "Controls: U - Undo, Ctrl+R - Redo, Arrows/Home/End - Browse moves, Ctrl+N - New Game, Enter - Type a move",
+            self.layout.x(BOARD_OFFSET_X),
+            self.layout.y(BOARD_OFFSET_Y + BOARD_SIZE + 68.0),
+            self.layout.size(20.0),
             GRAY,
         );
+
+        draw_ui_text(
+            "Ctrl+C/V - Copy/Paste FEN, Ctrl+S - Save PGN, E - Computer, T - Clock",
+            self.layout.x(BOARD_OFFSET_X),
+            self.layout.y(BOARD_OFFSET_Y + BOARD_SIZE + 92.0),
+            self.layout.size(20.0),
+            GRAY,
+        );
+    }
+
+    /// Each player's clock next to their side of the board, with the running
+    /// one highlighted.
+    fn draw_clocks(&self) {
+        let Some(clock) = &self.clock else {
+            return;
+        };
+
+        let now = Instant::now();
+        let layout = &self.layout;
+        let (top, bottom) = if self.flipped {
+            (Color::White, Color::Black)
+        } else {
+            (Color::Black, Color::White)
+        };
+        for (color, rect) in [(top, layout.clock(true)), (bottom, layout.clock(false))] {
+            let remaining = clock.remaining(color, now);
+            let running = clock.is_running() && clock.turn() == color;
+
+            let background = if running {
+                macroquad::color::Color::from_rgba(250, 240, 200, 255)
+            } else {
+                macroquad::color::Color::from_rgba(235, 235, 235, 255)
+            };
+            let text_color = if remaining < Duration::from_secs(20) {
+                macroquad::color::Color::from_rgba(160, 40, 40, 255)
+            } else {
+                BLACK
+            };
+
+            draw_rectangle(rect.x, rect.y, rect.w, rect.h, background);
+            draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, layout.size(2.0), if running { BLACK } else { GRAY });
+            draw_ui_text(
+                &clock_text(remaining),
+                rect.x + layout.size(8.0),
+                rect.y + layout.size(28.0),
+                layout.size(26.0),
+                text_color,
+            );
+        }
+    }
+
+    fn draw_buttons(&self) {
+        draw_button(&self.layout, self.layout.flip_button(), "Flip", false);
+        draw_button(&self.layout, self.layout.auto_orient_button(), "Auto", self.auto_orient);
+    }
+
+    /// Moves numbered in pairs, with the one that led to the position on the
+    /// board highlighted.
+    fn draw_move_list(&self) {
+        let layout = &self.layout;
+        let rect = layout.move_list();
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, macroquad::color::Color::from_rgba(245, 245, 245, 255));
+
+        let row_height = layout.size(MOVE_LIST_ROW_HEIGHT);
+        let font_size = layout.size(20.0);
+        let current_ply = self.game.current_ply();
+        let rows = self.move_list_scroll..(self.move_list_scroll + MOVE_LIST_ROWS).min(self.move_list.row_count());
+        for (line, row) in rows.enumerate() {
+            let y = rect.y + line as f32 * row_height;
+            let baseline = y + row_height - layout.size(6.0);
+            let move_number = self.move_list.first_move_number as usize + row;
+            draw_ui_text(&format!("{}.", move_number), rect.x + layout.size(8.0), baseline, font_size, GRAY);
+
+            for column in 0..2 {
+                let Some(ply) = (row * 2 + column).checked_sub(self.move_list.black_starts as usize) else {
+                    continue;
+                };
+                let Some(san) = self.move_list.san.get(ply) else {
+                    continue;
+                };
+
+                let x = rect.x + layout.size(MOVE_LIST_NUMBER_WIDTH + column as f32 * MOVE_LIST_SAN_WIDTH);
+                if ply + 1 == current_ply {
+                    draw_rectangle(
+                        x - layout.size(4.0),
+                        y + layout.size(2.0),
+                        layout.size(MOVE_LIST_SAN_WIDTH - 4.0),
+                        row_height - layout.size(4.0),
+                        macroquad::color::Color::from_rgba(200, 215, 235, 255),
+                    );
+                }
+                // Moves that were undone and can be redone are greyed out.
+                let color = if ply < current_ply { BLACK } else { GRAY };
+                draw_ui_text(san, x, baseline, font_size, color);
+            }
+        }
+
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, layout.size(2.0), GRAY);
+    }
+
+    fn draw_move_input(&self) {
+        let Some(input) = &self.move_input else {
+            return;
+        };
+
+        let layout = &self.layout;
+        let width = 220.0;
+        let rect = layout.rect(BOARD_OFFSET_X + BOARD_SIZE - width, BOARD_OFFSET_Y + BOARD_SIZE + 20.0, width, 30.0);
+
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, macroquad::color::Color::from_rgba(245, 245, 245, 255));
+        draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, layout.size(2.0), BLACK);
+        draw_ui_text(
+            &format!("Move: {}_", input),
+            rect.x + layout.size(8.0),
+            rect.y + layout.size(21.0),
+            layout.size(22.0),
+            BLACK,
+        );
     }
 
     fn screen_to_board_position(&self, screen_x: f32, screen_y: f32) -> Option<Position> {
-        let board_x = screen_x - BOARD_OFFSET_X;
-        let board_y = screen_y - BOARD_OFFSET_Y;
+        let square_size = self.layout.square_size();
+        let board = self.layout.board();
+        let board_x = screen_x - board.x;
+        let board_y = screen_y - board.y;
 
-        if board_x >= 0.0 && board_x < BOARD_SIZE && board_y >= 0.0 && board_y < BOARD_SIZE {
-            let col = (board_x / SQUARE_SIZE) as usize;
-            let row = (board_y / SQUARE_SIZE) as usize;
-            Position::new(row, col)
+        if (0.0..board.w).contains(&board_x) && (0.0..board.h).contains(&board_y) {
+            let col = (board_x / square_size) as usize;
+            let row = (board_y / square_size) as usize;
+            if self.flipped {
+                Position::new(7 - row, 7 - col)
+            } else {
+                Position::new(row, col)
+            }
         } else {
             None
         }
     }
 
     fn board_to_screen_position(&self, pos: Position) -> (f32, f32) {
-        let x = BOARD_OFFSET_X + pos.col as f32 * SQUARE_SIZE;
-        let y = BOARD_OFFSET_Y + pos.row as f32 * SQUARE_SIZE;
+        let square_size = self.layout.square_size();
+        let (row, col) = if self.flipped {
+            (7 - pos.row, 7 - pos.col)
+        } else {
+            (pos.row, pos.col)
+        };
+        let board = self.layout.board();
+        let x = board.x + col as f32 * square_size;
+        let y = board.y + row as f32 * square_size;
         (x, y)
     }
 }
+
+/// A labelled box; `active` buttons are drawn pressed in.
+fn draw_button(layout: &Layout, rect: Rect, label: &str, active: bool) {
+    let background = if active {
+        macroquad::color::Color::from_rgba(200, 215, 235, 255)
+    } else {
+        macroquad::color::Color::from_rgba(235, 235, 235, 255)
+    };
+    draw_rectangle(rect.x, rect.y, rect.w, rect.h, background);
+    draw_rectangle_lines(rect.x, rect.y, rect.w, rect.h, layout.size(2.0), if active { BLACK } else { GRAY });
+
+    let font_size = layout.size(20.0);
+    let size = measure_ui_text(label, font_size);
+    draw_ui_text(label, rect.x + (rect.w - size.width) / 2.0, rect.y + rect.h / 2.0 + size.offset_y / 2.0, font_size, BLACK);
+}
+
+/// Draws text rasterised at the screen's pixel density, so that it stays sharp
+/// on high-DPI displays instead of being scaled up.
+fn draw_ui_text(text: &str, x: f32, y: f32, font_size: f32, color: macroquad::color::Color) {
+    let dpi_scale = screen_dpi_scale();
+    draw_text_ex(
+        text,
+        x,
+        y,
+        TextParams {
+            font_size: (font_size * dpi_scale).round() as u16,
+            font_scale: 1.0 / dpi_scale,
+            color,
+            ..Default::default()
+        },
+    );
+}
+
+fn measure_ui_text(text: &str, font_size: f32) -> TextDimensions {
+    let dpi_scale = screen_dpi_scale();
+    measure_text(text, None, (font_size * dpi_scale).round() as u16, 1.0 / dpi_scale)
+}
+
+/// Red circles stacked so that they fade out towards the edge of the square.
+fn draw_check_glow(center_x: f32, center_y: f32, square_size: f32) {
+    for step in 0..6 {
+        let radius = square_size * (0.5 - step as f32 * 0.07);
+        draw_circle(center_x, center_y, radius, macroquad::color::Color::from_rgba(230, 30, 30, 55));
+    }
+}
+
+/// `h:mm:ss` from an hour up, `m:ss` below, and tenths of a second in the
+/// last 20 seconds.
+fn clock_text(time: Duration) -> String {
+    let seconds = time.as_secs();
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds / 60 % 60, seconds % 60)
+    } else if seconds >= 20 {
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    } else {
+        format!("0:{:02}.{}", seconds, time.subsec_millis() / 100)
+    }
+}
+
+/// Converts a Unix timestamp to a UTC (year, month, day) using Howard
+/// Hinnant's `civil_from_days` algorithm.
+fn civil_date_from_unix(seconds: u64) -> (i64, u32, u32) {
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
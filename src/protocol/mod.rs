@@ -0,0 +1,5 @@
+pub mod uci;
+pub mod xboard;
+
+pub use uci::*;
+pub use xboard::*;
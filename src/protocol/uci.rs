@@ -0,0 +1,274 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::domain::{Color, Game, SearchLimits, SearchResult, Searcher};
+
+/// Time kept back on every move for GUI and process overhead.
+const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// Moves to plan for when the GUI doesn't say how many are left until the
+/// next time control.
+pub(super) const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Reads UCI commands from `input` until `quit` or end of input, writing the
+/// engine's replies to `output`.
+pub fn run_uci<W: Write + Send + 'static>(input: impl BufRead, output: W) {
+    let mut engine = UciEngine::new(output);
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle_command(&line) {
+            break;
+        }
+    }
+    engine.stop_search();
+}
+
+/// State of a UCI session: the position set by the GUI and the search
+/// running on it, if any. Searches run on their own thread so that `stop` and
+/// `isready` are answered while thinking.
+pub struct UciEngine<W: Write + Send + 'static> {
+    game: Game,
+    output: Arc<Mutex<W>>,
+    search: Option<RunningSearch>,
+}
+
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl<W: Write + Send + 'static> UciEngine<W> {
+    pub fn new(output: W) -> Self {
+        UciEngine {
+            game: Game::new(),
+            output: Arc::new(Mutex::new(output)),
+            search: None,
+        }
+    }
+
+    /// Handles one line from the GUI. Returns false once the GUI sent `quit`.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("uci") => {
+                self.send("id name Rusty Chess");
+                self.send("id author javekk");
+                self.send("uciok");
+            }
+            Some("isready") => self.send("readyok"),
+            Some("ucinewgame") => {
+                self.stop_search();
+                self.game = Game::new();
+            }
+            Some("position") => {
+                self.stop_search();
+                let tokens: Vec<&str> = tokens.collect();
+                if let Err(error) = self.set_position(&tokens) {
+                    self.send(&format!("info string {}", error));
+                }
+            }
+            Some("go") => {
+                self.stop_search();
+                let tokens: Vec<&str> = tokens.collect();
+                self.start_search(&tokens);
+            }
+            Some("stop") => self.stop_search(),
+            Some("quit") => return false,
+            // `debug`, `setoption`, `register` and `ponderhit` need no answer,
+            // and unknown commands are to be ignored.
+            _ => {}
+        }
+        true
+    }
+
+    /// Ends the current search, if any, after it has sent its `bestmove`.
+    pub fn stop_search(&mut self) {
+        if let Some(search) = self.search.take() {
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.handle.join();
+        }
+    }
+
+    /// `position [startpos | fen <fen>] [moves <move>...]`
+    fn set_position(&mut self, tokens: &[&str]) -> Result<(), String> {
+        let moves_at = tokens.iter().position(|&token| token == "moves").unwrap_or(tokens.len());
+        let (setup, moves) = tokens.split_at(moves_at);
+
+        let mut game = match setup.split_first() {
+            Some((&"startpos", _)) => Game::new(),
+            Some((&"fen", fen)) => Game::from_fen(&fen.join(" ")).map_err(|error| format!("invalid FEN: {}", error))?,
+            _ => return Err("expected startpos or fen".to_string()),
+        };
+
+        for uci in moves.iter().skip(1) {
+            game.apply_uci_move(uci).map_err(|error| format!("illegal move {}: {}", uci, error))?;
+        }
+
+        self.game = game;
+        Ok(())
+    }
+
+    /// `go` with any of `depth`, `nodes`, `movetime`, `wtime`, `btime`,
+    /// `winc`, `binc`, `movestogo` and `infinite`.
+    fn start_search(&mut self, tokens: &[&str]) {
+        let value = |name: &str| -> Option<u64> {
+            let at = tokens.iter().position(|&token| token == name)?;
+            tokens.get(at + 1)?.parse().ok()
+        };
+        let infinite = tokens.contains(&"infinite");
+
+        let mut limits = SearchLimits {
+            depth: value("depth").map(|depth| depth as u32),
+            nodes: value("nodes"),
+            move_time: value("movetime").map(Duration::from_millis),
+        };
+
+        let (time_left, increment) = match self.game.current_player() {
+            Color::White => (value("wtime"), value("winc")),
+            Color::Black => (value("btime"), value("binc")),
+        };
+        if !infinite
+            && limits.move_time.is_none()
+            && let Some(time_left) = time_left
+        {
+            let moves_to_go = value("movestogo").map_or(DEFAULT_MOVES_TO_GO, |moves| moves.max(1) as u32);
+            limits.move_time = Some(allocate_time(
+                Duration::from_millis(time_left),
+                Duration::from_millis(increment.unwrap_or(0)),
+                moves_to_go,
+            ));
+        }
+
+        let mut searcher = Searcher::new(limits);
+        let stop = searcher.stop_flag();
+        let game = self.game.clone();
+        let output = Arc::clone(&self.output);
+        let search_stop = Arc::clone(&stop);
+
+        let handle = thread::spawn(move || {
+            let result = searcher.search_with(&game, |result| {
+                send(&output, &info_line(result));
+            });
+
+            // In infinite mode the best move may only be sent after `stop`.
+            while infinite && !search_stop.load(Ordering::Relaxed) {
+                thread::sleep(Duration::from_millis(5));
+            }
+
+            let best_move = result.best_move.map_or("0000".to_string(), |mv| mv.to_uci());
+            send(&output, &format!("bestmove {}", best_move));
+        });
+
+        self.search = Some(RunningSearch { stop, handle });
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.output, line);
+    }
+}
+
+/// Writes `line` and flushes straight away, since the GUI waits for each
+/// reply before sending more.
+pub(super) fn send<W: Write>(output: &Mutex<W>, line: &str) {
+    if let Ok(mut output) = output.lock() {
+        let _ = writeln!(output, "{}", line);
+        let _ = output.flush();
+    }
+}
+
+/// An even share of the remaining time plus most of the increment, but never
+/// more than half of what is left.
+pub(super) fn allocate_time(time_left: Duration, increment: Duration, moves_to_go: u32) -> Duration {
+    let share = time_left / moves_to_go + increment * 3 / 4;
+    let budget = share.min(time_left / 2).saturating_sub(MOVE_OVERHEAD);
+    budget.max(Duration::from_millis(10))
+}
+
+fn info_line(result: &SearchResult) -> String {
+    let score = match result.mate_in() {
+        Some(moves) => format!("mate {}", moves),
+        None => format!("cp {}", result.score),
+    };
+    let millis = result.elapsed.as_millis();
+    let nps = result.nodes as u128 * 1000 / millis.max(1);
+    let pv: Vec<String> = result.principal_variation.iter().map(|mv| mv.to_uci()).collect();
+
+    format!(
+        "info depth {} score {} nodes {} nps {} time {} pv {}",
+        result.depth,
+        score,
+        result.nodes,
+        nps,
+        millis,
+        pv.join(" ")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `commands` to `engine`, lets any search run to its depth and
+    /// returns what the engine wrote meanwhile.
+    fn session(engine: &mut UciEngine<Vec<u8>>, commands: &[&str]) -> String {
+        for command in commands {
+            assert!(engine.handle_command(command));
+        }
+        if let Some(search) = engine.search.take() {
+            search.handle.join().unwrap();
+        }
+        let output = std::mem::take(&mut *engine.output.lock().unwrap());
+        String::from_utf8(output).unwrap()
+    }
+
+    #[test]
+    fn answers_the_handshake() {
+        let mut engine = UciEngine::new(Vec::new());
+        let output = session(&mut engine, &["uci", "isready"]);
+        assert!(output.contains("id name Rusty Chess\n"));
+        assert!(output.ends_with("uciok\nreadyok\n"));
+        assert!(!engine.handle_command("quit"));
+    }
+
+    #[test]
+    fn searches_the_position_to_a_legal_best_move() {
+        let mut engine = UciEngine::new(Vec::new());
+        let output = session(&mut engine, &["ucinewgame", "position startpos moves e2e4 e7e5 g1f3", "go depth 2"]);
+        assert!(output.contains("info depth 2 "));
+
+        let best_move = output
+            .lines()
+            .find_map(|line| line.strip_prefix("bestmove "))
+            .expect("no bestmove");
+        let mut game = engine.game.clone();
+        assert_eq!(game.current_player(), Color::Black);
+        assert!(game.apply_uci_move(best_move).is_ok(), "illegal bestmove {}", best_move);
+    }
+
+    #[test]
+    fn keeps_the_position_after_an_invalid_fen() {
+        let mut engine = UciEngine::new(Vec::new());
+        session(&mut engine, &["position startpos moves d2d4"]);
+        let fen = engine.game.to_fen();
+
+        let output = session(&mut engine, &["position fen 8/8/8 w - - 0 1"]);
+        assert!(output.starts_with("info string invalid FEN"));
+        assert_eq!(engine.game.to_fen(), fen);
+    }
+
+    #[test]
+    fn keeps_the_position_after_an_illegal_move() {
+        let mut engine = UciEngine::new(Vec::new());
+        session(&mut engine, &["position startpos moves d2d4"]);
+        let fen = engine.game.to_fen();
+
+        let output = session(&mut engine, &["position startpos moves e2e4 e2e4"]);
+        assert!(output.starts_with("info string illegal move e2e4"));
+        assert_eq!(engine.game.to_fen(), fen);
+    }
+}
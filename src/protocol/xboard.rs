@@ -0,0 +1,405 @@
+use std::io::{BufRead, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::domain::{Color, Game, GameStatus, Move, SearchLimits, SearchResult, Searcher};
+
+use super::uci::{DEFAULT_MOVES_TO_GO, allocate_time, send};
+
+/// Reads CECP (XBoard/WinBoard) commands from `input` until `quit` or end of
+/// input, writing the engine's replies to `output`.
+pub fn run_xboard<W: Write + Send + 'static>(input: impl BufRead, output: W) {
+    let mut engine = XboardEngine::new(output);
+    for line in input.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if !engine.handle_command(&line) {
+            break;
+        }
+    }
+    engine.stop_search(false);
+}
+
+/// Clock settings from `level`, `st`, `sd` and `time`.
+#[derive(Debug, Clone, Copy)]
+struct TimeControl {
+    /// Moves per time control period; 0 means the base time is for the whole game.
+    moves_per_session: u32,
+    increment: Duration,
+    /// Exact time per move set with `st`, overriding the clock.
+    time_per_move: Option<Duration>,
+    max_depth: Option<u32>,
+    /// The engine's remaining clock time as last reported by `time`.
+    time_left: Duration,
+}
+
+impl Default for TimeControl {
+    fn default() -> Self {
+        TimeControl {
+            moves_per_session: 40,
+            increment: Duration::ZERO,
+            time_per_move: None,
+            max_depth: None,
+            time_left: Duration::from_secs(300),
+        }
+    }
+}
+
+/// State of a CECP session. The game is shared with the search thread, which
+/// plays its move on it when done so that the engine can answer while it
+/// thinks.
+pub struct XboardEngine<W: Write + Send + 'static> {
+    game: Arc<Mutex<Game>>,
+    output: Arc<Mutex<W>>,
+    /// Side the engine plays; `None` in force mode.
+    engine_color: Option<Color>,
+    time_control: TimeControl,
+    /// Whether to send thinking output (`post`/`nopost`).
+    post: bool,
+    search: Option<RunningSearch>,
+}
+
+struct RunningSearch {
+    stop: Arc<AtomicBool>,
+    /// Cleared when the search is called off without making a move.
+    play_move: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl<W: Write + Send + 'static> XboardEngine<W> {
+    pub fn new(output: W) -> Self {
+        XboardEngine {
+            game: Arc::new(Mutex::new(Game::new())),
+            output: Arc::new(Mutex::new(output)),
+            engine_color: Some(Color::Black),
+            time_control: TimeControl::default(),
+            post: false,
+            search: None,
+        }
+    }
+
+    /// Handles one CECP command. Returns false after `quit`, telling the caller
+    /// to stop reading input.
+    pub fn handle_command(&mut self, line: &str) -> bool {
+        let line = line.trim();
+        let (command, arguments) = line.split_once(' ').unwrap_or((line, ""));
+        let arguments = arguments.trim();
+
+        match command {
+            "protover" => {
+                self.send("feature ping=1 setboard=1 usermove=1 playother=1 time=1 draw=0 sigint=0 sigterm=0 reuse=1 analyze=0 colors=0 san=0 myname=\"Rusty Chess\"");
+                self.send("feature done=1");
+            }
+            "new" => {
+                self.stop_search(false);
+                self.set_game(Game::new());
+                self.engine_color = Some(Color::Black);
+                self.time_control.max_depth = None;
+            }
+            "force" => {
+                self.stop_search(false);
+                self.engine_color = None;
+            }
+            "go" => {
+                self.stop_search(false);
+                self.engine_color = Some(self.with_game(|game| game.current_player()));
+                self.think_if_engine_to_move();
+            }
+            "playother" => {
+                self.stop_search(false);
+                self.engine_color = Some(self.with_game(|game| game.current_player()).opposite());
+            }
+            "usermove" => self.user_move(arguments),
+            "?" => self.stop_search(true),
+            "setboard" => {
+                self.stop_search(false);
+                match Game::from_fen(arguments) {
+                    Ok(game) => self.set_game(game),
+                    Err(error) => self.send(&format!("tellusererror Illegal position: {}", error)),
+                }
+            }
+            "undo" => {
+                self.stop_search(false);
+                self.with_game(Game::undo_move);
+            }
+            "remove" => {
+                self.stop_search(false);
+                self.with_game(|game| game.undo_move() && game.undo_move());
+            }
+            "level" => self.set_level(arguments),
+            "st" => {
+                if let Ok(seconds) = arguments.parse::<f64>() {
+                    self.time_control.time_per_move = Some(Duration::from_secs_f64(seconds.max(0.0)));
+                }
+            }
+            "sd" => self.time_control.max_depth = arguments.parse().ok(),
+            "time" => {
+                if let Ok(centiseconds) = arguments.parse::<u64>() {
+                    self.time_control.time_left = Duration::from_millis(centiseconds * 10);
+                }
+            }
+            "result" => {
+                self.stop_search(false);
+                self.engine_color = None;
+            }
+            "ping" => self.send(&format!("pong {}", arguments)),
+            "post" => self.post = true,
+            "nopost" => self.post = false,
+            "quit" => return false,
+            // `xboard`, `accepted`, `rejected`, `otim`, `random`, `hard`,
+            // `easy`, `computer` and `name` need no answer.
+            "xboard" | "accepted" | "rejected" | "otim" | "random" | "hard" | "easy" | "computer" | "name" | "" => {}
+            // GUIs that didn't accept `usermove=1` send bare moves.
+            _ if Move::from_uci(command).is_some() => self.user_move(command),
+            _ => self.send(&format!("Error (unknown command): {}", command)),
+        }
+        true
+    }
+
+    /// Ends the current search. With `play_move` the engine still plays the
+    /// best move found so far (`?`), otherwise the search is dropped.
+    pub fn stop_search(&mut self, play_move: bool) {
+        if let Some(search) = self.search.take() {
+            search.play_move.store(play_move, Ordering::Relaxed);
+            search.stop.store(true, Ordering::Relaxed);
+            let _ = search.handle.join();
+        }
+    }
+
+    fn with_game<T>(&self, f: impl FnOnce(&mut Game) -> T) -> T {
+        let mut game = self.game.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        f(&mut game)
+    }
+
+    fn set_game(&mut self, game: Game) {
+        self.with_game(|current| *current = game);
+    }
+
+    fn user_move(&mut self, uci: &str) {
+        self.stop_search(false);
+        match self.with_game(|game| game.apply_uci_move(uci)) {
+            Ok(()) => self.think_if_engine_to_move(),
+            Err(error) => self.send(&format!("Illegal move ({}): {}", error, uci)),
+        }
+    }
+
+    /// `level MPS BASE INC`, with the base time in minutes or `minutes:seconds`
+    /// and the increment in seconds.
+    fn set_level(&mut self, arguments: &str) {
+        let fields: Vec<&str> = arguments.split_whitespace().collect();
+        let [moves, base, increment] = fields[..] else {
+            self.send(&format!("Error (bad level): {}", arguments));
+            return;
+        };
+
+        let base = match base.split_once(':') {
+            Some((minutes, seconds)) => minutes.parse::<u64>().ok().zip(seconds.parse::<u64>().ok()).map(|(m, s)| m * 60 + s),
+            None => base.parse::<u64>().ok().map(|minutes| minutes * 60),
+        };
+        match (moves.parse(), base, increment.parse::<f64>()) {
+            (Ok(moves), Some(base), Ok(increment)) => {
+                self.time_control.moves_per_session = moves;
+                self.time_control.time_left = Duration::from_secs(base);
+                self.time_control.increment = Duration::from_secs_f64(increment.max(0.0));
+                self.time_control.time_per_move = None;
+            }
+            _ => self.send(&format!("Error (bad level): {}", arguments)),
+        }
+    }
+
+    fn search_limits(&self, fullmove_number: u32) -> SearchLimits {
+        let control = self.time_control;
+        let move_time = control.time_per_move.unwrap_or_else(|| {
+            let moves_to_go = match control.moves_per_session {
+                0 => DEFAULT_MOVES_TO_GO,
+                session => session - (fullmove_number - 1) % session,
+            };
+            allocate_time(control.time_left, control.increment, moves_to_go)
+        });
+
+        SearchLimits { depth: control.max_depth, move_time: Some(move_time), nodes: None }
+    }
+
+    fn think_if_engine_to_move(&mut self) {
+        let game = self.with_game(|game| game.clone());
+        if self.engine_color != Some(game.current_player()) || game.status().is_game_over() {
+            return;
+        }
+
+        let mut searcher = Searcher::new(self.search_limits(game.fullmove_number()));
+        let stop = searcher.stop_flag();
+        let play_move = Arc::new(AtomicBool::new(true));
+        let shared_game = Arc::clone(&self.game);
+        let output = Arc::clone(&self.output);
+        let post = self.post;
+        let search_play_move = Arc::clone(&play_move);
+
+        let handle = thread::spawn(move || {
+            let result = searcher.search_with(&game, |result| {
+                if post {
+                    send(&output, &thinking_line(result));
+                }
+            });
+
+            let Some(mv) = result.best_move else {
+                return;
+            };
+            if !search_play_move.load(Ordering::Relaxed) {
+                return;
+            }
+
+            let mut game = shared_game.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if game.make_move(mv).is_ok() {
+                send(&output, &format!("move {}", mv.to_uci()));
+                if let Some(result) = result_line(game.status()) {
+                    send(&output, &result);
+                }
+            }
+        });
+
+        self.search = Some(RunningSearch { stop, play_move, handle });
+    }
+
+    fn send(&self, line: &str) {
+        send(&self.output, line);
+    }
+}
+
+/// `ply score time nodes pv`, with the time in centiseconds and mate in N
+/// moves scored as 100000 + N.
+fn thinking_line(result: &SearchResult) -> String {
+    let score = match result.mate_in() {
+        Some(moves) if moves > 0 => 100_000 + moves,
+        Some(moves) => -100_000 + moves,
+        None => result.score,
+    };
+    let pv: Vec<String> = result.principal_variation.iter().map(|mv| mv.to_uci()).collect();
+    format!("{} {} {} {} {}", result.depth, score, result.elapsed.as_millis() / 10, result.nodes, pv.join(" "))
+}
+
+/// The result announcement for a finished game, e.g. `1-0 {White mates}`.
+fn result_line(status: GameStatus) -> Option<String> {
+    let comment = match status {
+        GameStatus::Checkmate(Color::White) => "White mates".to_string(),
+        GameStatus::Checkmate(Color::Black) => "Black mates".to_string(),
+        GameStatus::TimeForfeit(Color::White) => "Black forfeits on time".to_string(),
+        GameStatus::TimeForfeit(Color::Black) => "White forfeits on time".to_string(),
+        GameStatus::Draw(reason) => format!("Draw by {}", reason.description()),
+        GameStatus::Ongoing | GameStatus::Check => return None,
+    };
+    Some(format!("{} {{{}}}", status.pgn_result(), comment))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feeds `commands` to `engine`, lets any search finish and returns what
+    /// the engine wrote meanwhile.
+    fn session(engine: &mut XboardEngine<Vec<u8>>, commands: &[&str]) -> String {
+        for command in commands {
+            assert!(engine.handle_command(command));
+        }
+        if let Some(search) = engine.search.take() {
+            search.handle.join().unwrap();
+        }
+        let output = std::mem::take(&mut *engine.output.lock().unwrap());
+        String::from_utf8(output).unwrap()
+    }
+
+    fn fen(engine: &XboardEngine<Vec<u8>>) -> String {
+        engine.with_game(|game| game.to_fen())
+    }
+
+    #[test]
+    fn negotiates_features() {
+        let mut engine = XboardEngine::new(Vec::new());
+        let output = session(&mut engine, &["xboard", "protover 2", "ping 3"]);
+        let features = output.lines().next().unwrap();
+        assert!(features.starts_with("feature "));
+        for feature in ["ping=1", "setboard=1", "usermove=1", "playother=1", "time=1"] {
+            assert!(features.contains(feature), "missing {}", feature);
+        }
+        assert!(output.ends_with("feature done=1\npong 3\n"));
+    }
+
+    #[test]
+    fn answers_a_user_move() {
+        let mut engine = XboardEngine::new(Vec::new());
+        let output = session(&mut engine, &["new", "sd 2", "usermove e2e4"]);
+
+        let reply = output.lines().find_map(|line| line.strip_prefix("move ")).expect("no move");
+        let mut game = Game::new();
+        game.apply_uci_move("e2e4").unwrap();
+        game.apply_uci_move(reply).unwrap();
+        assert_eq!(fen(&engine), game.to_fen());
+    }
+
+    #[test]
+    fn waits_in_force_mode_until_go() {
+        let mut engine = XboardEngine::new(Vec::new());
+        let output = session(&mut engine, &["new", "force", "usermove e2e4", "usermove e7e5"]);
+        assert_eq!(output, "");
+
+        let output = session(&mut engine, &["sd 2", "go"]);
+        assert_eq!(output.lines().filter(|line| line.starts_with("move ")).count(), 1);
+        assert_eq!(engine.engine_color, Some(Color::White));
+        assert_eq!(engine.with_game(|game| game.current_player()), Color::Black);
+    }
+
+    #[test]
+    fn rejects_a_bad_fen() {
+        let mut engine = XboardEngine::new(Vec::new());
+        session(&mut engine, &["new", "force", "usermove d2d4"]);
+        let before = fen(&engine);
+
+        let output = session(&mut engine, &["setboard 8/8/8 w - - 0 1"]);
+        assert!(output.starts_with("tellusererror Illegal position"));
+        assert_eq!(fen(&engine), before);
+    }
+
+    #[test]
+    fn undo_and_remove_take_moves_back() {
+        let mut engine = XboardEngine::new(Vec::new());
+        session(&mut engine, &["new", "force", "usermove e2e4", "usermove e7e5"]);
+        let after_two_moves = fen(&engine);
+
+        session(&mut engine, &["usermove g1f3", "undo"]);
+        assert_eq!(fen(&engine), after_two_moves);
+
+        session(&mut engine, &["remove"]);
+        assert_eq!(fen(&engine), Game::new().to_fen());
+    }
+
+    #[test]
+    fn reads_time_controls() {
+        let mut engine = XboardEngine::new(Vec::new());
+        assert_eq!(session(&mut engine, &["level 40 5 0"]), "");
+        assert_eq!(engine.time_control.moves_per_session, 40);
+        assert_eq!(engine.time_control.time_left, Duration::from_secs(300));
+        assert_eq!(engine.time_control.increment, Duration::ZERO);
+
+        session(&mut engine, &["level 0 2:30 5", "sd 6"]);
+        assert_eq!(engine.time_control.moves_per_session, 0);
+        assert_eq!(engine.time_control.time_left, Duration::from_secs(150));
+        assert_eq!(engine.time_control.increment, Duration::from_secs(5));
+        assert_eq!(engine.time_control.max_depth, Some(6));
+
+        session(&mut engine, &["st 10"]);
+        assert_eq!(engine.time_control.time_per_move, Some(Duration::from_secs(10)));
+        assert_eq!(engine.search_limits(1).move_time, Some(Duration::from_secs(10)));
+    }
+
+    #[test]
+    fn reports_a_bad_level() {
+        let mut engine = XboardEngine::new(Vec::new());
+        session(&mut engine, &["level 40 5 0"]);
+
+        assert_eq!(session(&mut engine, &["level 40 5"]), "Error (bad level): 40 5\n");
+        assert_eq!(session(&mut engine, &["level 40 five 0"]), "Error (bad level): 40 five 0\n");
+        assert_eq!(engine.time_control.time_left, Duration::from_secs(300));
+    }
+}
@@ -0,0 +1,164 @@
+//! Reading PGN movetext and writing it back out.
+
+use std::time::{Duration, Instant};
+
+use rusty_chess::Game;
+use rusty_chess::domain::{Clock, PgnErrorKind, PgnTags, SanError, TimeControl, parse_pgn};
+
+fn play(game: &mut Game, san: &str) {
+    let mv = game.parse_san(san).unwrap();
+    game.make_move(mv).unwrap();
+}
+
+#[test]
+fn skips_comments_nags_and_escaped_lines() {
+    let pgn = "\
+% exported by some program
+[Event \"Test\"]
+
+1. e4 {best by test} e5 $1 ; a rest-of-line comment 2. Nf3
+2. Nc3
+% 2... Nc6
+2... Nf6 3. f4 *
+";
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].tag("Event"), Some("Test"));
+    assert_eq!(games[0].game.san_history(), ["e4", "e5", "Nc3", "Nf6", "f4"]);
+    assert_eq!(games[0].result, "*");
+}
+
+#[test]
+fn skips_nested_variations() {
+    let pgn = "1. e4 e5 (1... c5 2. Nf3 (2. c3 d5) d6) 2. Nf3 (2. Bc4 [%csl Ge4] Nf6) Nc6 1-0";
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(games[0].game.san_history(), ["e4", "e5", "Nf3", "Nc6"]);
+    assert_eq!(games[0].result, "1-0");
+}
+
+#[test]
+fn reads_multiple_games() {
+    let pgn = "\
+[White \"A\"]
+
+1. d4 d5 1/2-1/2
+
+[White \"B\"]
+
+1. c4 0-1
+";
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(games.len(), 2);
+    assert_eq!(games[0].tag("White"), Some("A"));
+    assert_eq!(games[0].game.san_history(), ["d4", "d5"]);
+    assert_eq!(games[0].result, "1/2-1/2");
+    assert_eq!(games[1].tag("White"), Some("B"));
+    assert_eq!(games[1].game.san_history(), ["c4"]);
+    assert_eq!(games[1].result, "0-1");
+}
+
+#[test]
+fn tag_values_may_contain_brackets_and_quotes() {
+    let pgn = "[Event \"Blitz [final]\"]\n[Site \"The \\\"Hall\\\"\"]\n\n1. e4 *";
+    let games = parse_pgn(pgn).unwrap();
+    assert_eq!(games[0].tag("Event"), Some("Blitz [final]"));
+    assert_eq!(games[0].tag("Site"), Some("The \"Hall\""));
+    assert_eq!(games[0].game.san_history(), ["e4"]);
+}
+
+#[test]
+fn reports_illegal_moves() {
+    let error = parse_pgn("1. e4 e5 1-0\n\n1. d4 d5 2. Ke3 *").unwrap_err();
+    assert_eq!(error.game_index, 1);
+    assert_eq!(error.ply, 3);
+    assert_eq!(error.token, "Ke3");
+    assert!(matches!(error.kind, PgnErrorKind::InvalidMove(SanError::Illegal(_))));
+}
+
+#[test]
+fn reports_unterminated_comments() {
+    let error = parse_pgn("1. e4 e5 {never closed 2. Nf3 *").unwrap_err();
+    assert_eq!((error.game_index, error.ply, error.token.as_str()), (0, 2, "{"));
+    assert_eq!(error.kind, PgnErrorKind::UnterminatedComment);
+}
+
+#[test]
+fn reports_unbalanced_variations() {
+    let error = parse_pgn("1. e4 (1. d4 d5 *").unwrap_err();
+    assert_eq!((error.game_index, error.ply, error.token.as_str()), (0, 1, "("));
+    assert_eq!(error.kind, PgnErrorKind::UnbalancedVariation);
+
+    let error = parse_pgn("1. e4 e5 2. Nf3 ) Nc6 *").unwrap_err();
+    assert_eq!((error.game_index, error.ply, error.token.as_str()), (0, 3, ")"));
+    assert_eq!(error.kind, PgnErrorKind::UnbalancedVariation);
+}
+
+#[test]
+fn reports_a_stray_closing_brace() {
+    let error = parse_pgn("1. e4 } e5 *").unwrap_err();
+    assert_eq!((error.game_index, error.ply, error.token.as_str()), (0, 1, "}"));
+    assert_eq!(error.kind, PgnErrorKind::UnexpectedToken);
+}
+
+#[test]
+fn skips_brackets_inside_variations() {
+    let games = parse_pgn("1. e4 (1. d4 [x]) e5 *").unwrap();
+    assert_eq!(games[0].game.san_history(), ["e4", "e5"]);
+}
+
+#[test]
+fn round_trips_with_clock_comments() {
+    let start = Instant::now();
+    let mut game = Game::new();
+    let mut clock = Clock::new(
+        TimeControl::increment(Duration::from_secs(300), Duration::from_secs(2)),
+        game.current_player(),
+    );
+    for (san, seconds) in [("e4", 0), ("e5", 5), ("Nf3", 7), ("Nc6", 8)] {
+        play(&mut game, san);
+        clock.press(start + Duration::from_secs(seconds));
+    }
+
+    let tags = PgnTags {
+        white: "Alice".to_string(),
+        ..PgnTags::default()
+    };
+    let pgn = game.to_pgn_with_clock(&tags, &clock);
+    assert!(pgn.contains("[TimeControl \"300+2\"]"));
+    assert!(pgn.ends_with(
+        "\n1. e4 {[%clk 0:05:02]} 1... e5 {[%clk 0:04:57]} 2. Nf3 {[%clk 0:05:02]} 2... Nc6\n{[%clk 0:04:58]} *\n"
+    ));
+
+    let games = parse_pgn(&pgn).unwrap();
+    assert_eq!(games.len(), 1);
+    assert_eq!(games[0].tag("White"), Some("Alice"));
+    assert_eq!(games[0].tag("TimeControl"), Some("300+2"));
+    assert_eq!(games[0].game.san_history(), game.san_history());
+    assert_eq!(games[0].game.to_fen(), game.to_fen());
+    assert_eq!(games[0].result, "*");
+}
+
+#[test]
+fn clock_comments_follow_the_ply_they_were_recorded_at() {
+    let start = Instant::now();
+    let control = TimeControl::increment(Duration::from_secs(300), Duration::from_secs(2));
+    let mut game = Game::new();
+    play(&mut game, "d4");
+    play(&mut game, "d5");
+
+    // The clock is set up after two moves, which get no comment.
+    let mut clock = Clock::for_game(control, &game);
+    for (san, seconds) in [("c4", 0), ("e6", 5), ("Nc3", 7), ("Nf6", 8)] {
+        play(&mut game, san);
+        clock.press(start + Duration::from_secs(seconds));
+    }
+    let pgn = game.to_pgn_with_clock(&PgnTags::default(), &clock);
+    assert!(pgn.ends_with(
+        "\n1. d4 d5 2. c4 {[%clk 0:05:02]} 2... e6 {[%clk 0:04:57]} 3. Nc3 {[%clk 0:05:02]}\n3... Nf6 {[%clk 0:04:58]} *\n"
+    ));
+
+    // Saving while looking at an earlier move keeps each time on its move.
+    game.go_to_ply(4);
+    let pgn = game.to_pgn_with_clock(&PgnTags::default(), &clock);
+    assert!(pgn.ends_with("\n1. d4 d5 2. c4 {[%clk 0:05:02]} 2... e6 {[%clk 0:04:57]} *\n"));
+}